@@ -0,0 +1,7 @@
+libs:
+input: 17 5
+expect: 3 2
+input: 20 4
+expect: 5 0
+input: 3 0
+expect:
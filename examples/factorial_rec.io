@@ -0,0 +1,7 @@
+libs: ../std.crmarie
+input: 5
+expect: 120
+input: 0
+expect: 1
+input: 7
+expect: 5040
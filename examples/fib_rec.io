@@ -0,0 +1,7 @@
+libs: ../std.crmarie
+input: 10
+expect: 55
+input: 1
+expect: 1
+input: 0
+expect: 0
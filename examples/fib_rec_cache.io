@@ -0,0 +1,3 @@
+libs: ../std.crmarie
+input: 20
+expect: 6765
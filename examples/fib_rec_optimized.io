@@ -0,0 +1,3 @@
+libs: ../std.crmarie
+input: 15
+expect: 610
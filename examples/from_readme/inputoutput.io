@@ -0,0 +1,3 @@
+libs: ../../std.crmarie
+input: 42
+expect: 42
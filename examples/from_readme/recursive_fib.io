@@ -0,0 +1,4 @@
+libs: ../../std.crmarie
+// main outputs x, not the computed number
+input: 10
+expect: 10
@@ -0,0 +1,4 @@
+libs: ../../std.crmarie
+// 1 would output 1 forever
+input: 0
+expect: 0
@@ -0,0 +1,6 @@
+libs: ../std.crmarie
+// gcd and least common multiple
+input: 12 18
+expect: 6 36
+input: 7 5
+expect: 1 35
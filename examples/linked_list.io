@@ -0,0 +1,5 @@
+libs: ../std.crmarie
+input: 1 2 3 0
+expect: 3 2 1
+input: 0
+expect:
@@ -0,0 +1,8 @@
+libs: ../std.crmarie
+// size followed by the numbers
+input: 5 3 1 4 5 2
+expect: 1 2 3 4 5
+input: 8 -3 10 0 7 7 2 100 -50
+expect: -50 -3 0 2 7 7 10 100
+input: 1 7
+expect: 7
@@ -1,8 +1,43 @@
 type ArgumentMapFunction<T> = fn(&[String], &mut T) -> Result<(), String>;
-#[derive(derive_new::new, Clone)]
+#[derive(Clone)]
 pub struct ArgumentMapping<T> {
-    pub letter: char,
+    pub letter: Option<char>,
     pub map_fnc: ArgumentMapFunction<T>,
+    pub long: Option<&'static str>, //"--long" works same as "-letter"
+    pub repeatable: bool,           //map_fnc is called for every occurrence
+}
+impl<T> ArgumentMapping<T> {
+    pub fn new(letter: char, map_fnc: ArgumentMapFunction<T>) -> Self {
+        return Self {
+            letter: Some(letter),
+            map_fnc,
+            long: None,
+            repeatable: false,
+        };
+    }
+    pub fn long_only(long: &'static str, map_fnc: ArgumentMapFunction<T>) -> Self {
+        return Self {
+            letter: None,
+            map_fnc,
+            long: Some(long),
+            repeatable: false,
+        };
+    }
+    pub fn with_long(mut self, long: &'static str) -> Self {
+        self.long = Some(long);
+        return self;
+    }
+    pub fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        return self;
+    }
+    fn matches(&self, arg: &str) -> bool {
+        if let Some(long) = arg.strip_prefix("--") {
+            return self.long == Some(long);
+        }
+        let chars: Vec<char> = arg.chars().collect();
+        return chars.len() == 2 && chars[0] == '-' && Some(chars[1]) == self.letter;
+    }
 }
 
 pub fn parse_arguments<T>(
@@ -35,15 +70,14 @@ pub fn parse_arguments<T>(
 
     for i in start_index..args.len() {
         if maybe_mapper.is_none() {
-            let chars: Vec<char> = args[i].chars().collect();
-            if chars.len() != 2 || chars[0] != '-' {
+            if !args[i].starts_with('-') {
                 return Err(
                     "Argument passing doesn't follow format \"-{{letter}}content\" ".to_owned(),
                 );
             }
             for (el_index, el) in mappers.iter().enumerate() {
-                if el.letter == chars[1] {
-                    if already_done[el_index] {
+                if el.matches(&args[i]) {
+                    if already_done[el_index] && !el.repeatable {
                         return Err(format!("Specified twice: {}", args[i]));
                     }
                     already_done[el_index] = true;
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use crate::utility::LinedError;
+
+pub const MEMORY_SIZE: usize = 4096;
+
+///mnemonic and its opcode
+const INSTRUCTIONS: [(&str, u16); 15] = [
+    ("jns", 0x0),
+    ("load", 0x1),
+    ("store", 0x2),
+    ("add", 0x3),
+    ("subt", 0x4),
+    ("input", 0x5),
+    ("output", 0x6),
+    ("halt", 0x7),
+    ("skipcond", 0x8),
+    ("jump", 0x9),
+    ("clear", 0xA),
+    ("addi", 0xB),
+    ("jumpi", 0xC),
+    ("loadi", 0xD),
+    ("storei", 0xE),
+];
+const NO_OPERAND: [&str; 4] = ["input", "output", "halt", "clear"];
+
+pub type AssemblerError = LinedError<String>;
+
+///machine code, index of the word is its address
+#[derive(Debug, Default)]
+pub struct Assembled {
+    pub memory: Vec<u16>,
+    pub labels: HashMap<String, usize>,
+    pub lines: Vec<usize>, //line of marie code each word comes from
+}
+
+///"label, instruction operand / comment"
+struct AsmLine<'a> {
+    label: Option<&'a str>,
+    instruction: String,
+    operand: Option<&'a str>,
+    line: usize,
+}
+
+fn split_line(text: &str, line: usize) -> Result<Option<AsmLine<'_>>, String> {
+    let text = text.split('/').next().unwrap().trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let (label, rest) = match text.split_once(',') {
+        Some((label, rest)) => (Some(label.trim()), rest),
+        None => (None, text),
+    };
+    let mut parts = rest.split_whitespace();
+    let instruction = parts
+        .next()
+        .ok_or_else(|| "expected instruction after label".to_owned())?
+        .to_lowercase();
+    let operand = parts.next();
+    if let Some(extra) = parts.next() {
+        return Err(format!("unexpected {extra} after operand"));
+    }
+    return Ok(Some(AsmLine {
+        label,
+        instruction,
+        operand,
+        line,
+    }));
+}
+fn parse_number(t: &str, radix: u32) -> Result<u16, String> {
+    let value = i32::from_str_radix(t, radix).map_err(|_| format!("{t} is not a number"))?;
+    if value < i16::MIN as i32 || value > u16::MAX as i32 {
+        return Err(format!("{t} doesn't fit in a word"));
+    }
+    return Ok(value as u16);
+}
+fn encode(asm: &AsmLine, labels: &HashMap<String, usize>) -> Result<u16, String> {
+    let radix = match asm.instruction.as_str() {
+        "dec" => Some(10),
+        "hex" => Some(16),
+        "oct" => Some(8),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        let operand = asm
+            .operand
+            .ok_or_else(|| format!("{} needs a value", asm.instruction))?;
+        return parse_number(operand, radix);
+    }
+    let opcode = INSTRUCTIONS
+        .iter()
+        .find(|e| e.0 == asm.instruction)
+        .ok_or_else(|| format!("unknown instruction {}", asm.instruction))?
+        .1;
+    let address = match asm.operand {
+        None if NO_OPERAND.contains(&asm.instruction.as_str()) => 0,
+        None => return Err(format!("{} needs an operand", asm.instruction)),
+        Some(operand) if NO_OPERAND.contains(&asm.instruction.as_str()) => {
+            return Err(format!(
+                "{} takes no operand, found {operand}",
+                asm.instruction
+            ))
+        }
+        Some(operand) => match labels.get(operand) {
+            Some(address) => *address as u16,
+            None => u16::from_str_radix(operand, 16)
+                .ok()
+                .filter(|e| (*e as usize) < MEMORY_SIZE)
+                .ok_or_else(|| format!("label {operand} not found"))?,
+        },
+    };
+    return Ok(opcode << 12 | address);
+}
+
+///operands are labels or hex addresses, one word per line
+pub fn assemble(code: &str) -> Result<Assembled, AssemblerError> {
+    let mut asm_lines = Vec::new();
+    for (i, text) in code.lines().enumerate() {
+        let split = split_line(text, i).map_err(|e| AssemblerError::new(i, text.to_owned(), e))?;
+        asm_lines.extend(split);
+    }
+    if asm_lines.len() > MEMORY_SIZE {
+        return Err(AssemblerError::general(format!(
+            "program takes {} words, only {MEMORY_SIZE} fit in memory",
+            asm_lines.len()
+        )));
+    }
+
+    let mut assembled = Assembled::default();
+    for (address, asm) in asm_lines.iter().enumerate() {
+        if let Some(label) = asm.label {
+            if assembled.labels.insert(label.to_owned(), address).is_some() {
+                return Err(AssemblerError::new(
+                    asm.line,
+                    String::new(),
+                    format!("label {label} is defined twice"),
+                ));
+            }
+        }
+    }
+    for asm in &asm_lines {
+        let word = encode(asm, &assembled.labels)
+            .map_err(|e| AssemblerError::new(asm.line, String::new(), e))?;
+        assembled.memory.push(word);
+        assembled.lines.push(asm.line);
+    }
+    return Ok(assembled);
+}
@@ -1,8 +1,9 @@
 use crate::{
     lexer::ConditionKind,
     parser::{
-        ArgumentCallArg, Function, FunctionCall, FunctionId, If, ProgramTree, Statement, Variable,
-        VariableId, VariableType,
+        get_operation_temp_name, ArgumentCallArg, DataType, FeatureFlags, FlagId, Function,
+        FunctionCall, FunctionId, If, InlinePart, LineStatement, Operation, OperationKind, Place,
+        ProgramTree, Statement, Switch, Variable, VariableId, VariableType,
     },
     string_builder::Builder,
 };
@@ -15,14 +16,66 @@ pub struct CompilerContext {
     element_counter: usize,
     flags: Vec<(String, usize)>,
     addrs: Vec<(usize, usize)>,
+    origins: Vec<CodeOrigin>,
 }
 impl CompilerContext {
     fn push_counter(&mut self) -> usize {
         self.element_counter += 1;
         return self.element_counter - 1;
     }
+    ///code starting at address comes from line of function fnc
+    fn push_origin(&mut self, address: usize, code: &str, line: usize, fnc: FunctionId) {
+        let size = get_size(code);
+        self.origins
+            .push(CodeOrigin::new(address, address + size, line, fnc));
+    }
+}
+///words from..to (exclusive) were compiled from the statement (or function) at line
+#[derive(Debug, Clone, Copy, PartialEq, derive_new::new)]
+pub struct CodeOrigin {
+    pub from: usize,
+    pub to: usize,
+    pub line: usize,
+    pub fnc: FunctionId,
+}
+///marie code with where it comes from
+#[derive(Debug, Default)]
+pub struct Compiled {
+    pub code: String,
+    pub origins: Vec<CodeOrigin>, //sorted by address, nested statements come after the ones containing them
+    pub address_map: HashMap<VariableId, usize>, //address of every variable (of the first field for structs)
+}
+///innermost statement the word at address comes from, none for code added by the compiler
+pub fn find_origin(origins: &[CodeOrigin], address: usize) -> Option<&CodeOrigin> {
+    return origins
+        .iter()
+        .filter(|e| e.from <= address && address < e.to)
+        .min_by_key(|e| e.to - e.from);
+}
+///words of the whole function, none for ghost functions
+pub fn find_function_range(origins: &[CodeOrigin], fnc: FunctionId) -> Option<(usize, usize)> {
+    return origins
+        .iter()
+        .filter(|e| e.fnc == fnc)
+        .max_by_key(|e| e.to - e.from)
+        .map(|e| (e.from, e.to));
+}
+///words the code takes, one per line
+fn get_size(code: &str) -> usize {
+    if code.is_empty() {
+        return 0;
+    }
+    return code.chars().filter(|e| *e == '\n').count() + 1;
 }
 pub fn var_decl_text(t: &Variable, tree: &ProgramTree) -> String {
+    if let DataType::Struct(id) = t.data_type {
+        //one word per field, the address of the variable is the address of first field
+        let fields = 0..tree.get_struct(id).fields.len();
+        return fields
+            .map(|e| format!("{}, DEC 0", get_field_text(t, e, tree)))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
     return format!("{}, DEC {}", get_var_text(t, tree), t.default_value);
 }
 pub fn constant_decl_text(t: i32) -> String {
@@ -34,10 +87,40 @@ pub fn get_var_text(t: &Variable, tree: &ProgramTree) -> String {
         VariableType::Local(id) => format!("var_{}_{}", tree.get_fnc(id).name, t.name),
     };
 }
+pub fn get_field_text(t: &Variable, field: usize, tree: &ProgramTree) -> String {
+    let fields = match t.data_type {
+        DataType::Struct(id) | DataType::StructPtr(id) => &tree.get_struct(id).fields,
+        DataType::Word | DataType::Int | DataType::Ptr => panic!("logic error"),
+    };
+    return format!("{}_{}", get_var_text(t, tree), fields[field]);
+}
 pub fn get_constant_text(n: i32) -> String {
     return format!("const_{n}");
 }
+pub fn get_flag_text(id: FlagId, tree: &ProgramTree) -> String {
+    return format!(
+        "flag_{}_{}",
+        tree.get_fnc(id.fnc).name,
+        tree.get_flag_name(id)
+    );
+}
+pub fn get_string_ptr_text(index: usize) -> String {
+    return format!("str_ptr_{index}");
+}
 
+fn get_inline_text(parts: &[InlinePart], context: &CompilerContext) -> String {
+    let tree = &context.tree;
+    return parts
+        .iter()
+        .map(|part| match part {
+            InlinePart::Text(text) => text.clone(),
+            InlinePart::Variable(id) => get_var_text(tree.get_var(*id), tree),
+            InlinePart::Field(id, field) => get_field_text(tree.get_var(*id), *field, tree),
+            InlinePart::Constant(n) => get_constant_text(*n),
+            InlinePart::Flag(id) => get_flag_text(*id, tree),
+        })
+        .collect();
+}
 fn get_load_from_arg_text(arg: &ArgumentCallArg, context: &CompilerContext) -> String {
     return match arg {
         ArgumentCallArg::Literal(v) => {
@@ -58,26 +141,94 @@ fn get_load_from_arg_text(arg: &ArgumentCallArg, context: &CompilerContext) -> S
         ArgumentCallArg::GetAddress(id) => {
             format!("load {}", get_constant_text(context.address_map[id] as i32))
         }
-        ArgumentCallArg::Flag(content, id) => {
-            format!("load flag_{}_{}", context.tree.get_fnc(*id).name, content)
+        ArgumentCallArg::Flag(id) => format!("load {}", get_flag_text(*id, &context.tree)),
+        ArgumentCallArg::Str(content) => {
+            let index = context.tree.find_string_index(content).unwrap(); //parser registers every string
+            format!("load {}", get_string_ptr_text(index))
         }
+        ArgumentCallArg::Operation(op) => get_operation_text(op, arg.height(), context),
+        ArgumentCallArg::Field(id, field) => {
+            let var = context.tree.get_var(*id);
+            format!("load {}", get_field_text(var, *field, &context.tree))
+        }
+        ArgumentCallArg::PtrField(id, field) => get_ptr_field_text(*id, *field, "loadi", context),
     };
 }
 
-fn get_store_text(arg: VariableId, context: &CompilerContext) -> String {
+///does the operation on the field p->x, operation can't change var__field_ptr
+fn get_ptr_field_text(
+    id: VariableId,
+    field: usize,
+    operation: &str,
+    context: &CompilerContext,
+) -> String {
+    let var_t = get_var_text(context.tree.get_var(id), &context.tree);
+    if field == 0 {
+        return format!("{operation} {var_t}");
+    }
+    let field_t = get_constant_text(field as i32);
     return format!(
-        "store {}",
-        get_var_text(context.tree.get_var(arg), &context.tree)
+        "load {var_t}\nadd {field_t}\nstore var__field_ptr\n{operation} var__field_ptr"
     );
 }
+
+///calls builtin helper, result is left in acc
+fn get_operation_text(op: &Operation, height: usize, context: &CompilerContext) -> String {
+    let (helper, result) = match op.kind {
+        OperationKind::Mul => ("_mul", "".to_owned()),
+        OperationKind::Div => ("_divmod", "".to_owned()),
+        OperationKind::Mod => ("_divmod", "\nload var__divmod_r".to_owned()),
+    };
+    let mut builder = Builder::new();
+    if op.b.height() > 0 {
+        //right side would overwrite arguments of the helper if evaluated after left side
+        let temp = format!("var_{}", get_operation_temp_name(height));
+        builder.push_line_smart(&get_load_from_arg_text(&op.b, context));
+        builder.push_line_smart(&format!("store {temp}"));
+        builder.push_line_smart(&get_load_from_arg_text(&op.a, context));
+        builder.push_line_smart(&format!("store var_{helper}_a"));
+        builder.push_line_smart(&format!("load {temp}\nstore var_{helper}_b"));
+    } else {
+        builder.push_line_smart(&get_load_from_arg_text(&op.a, context));
+        builder.push_line_smart(&format!("store var_{helper}_a"));
+        builder.push_line_smart(&get_load_from_arg_text(&op.b, context));
+        builder.push_line_smart(&format!("store var_{helper}_b"));
+    }
+    builder.push_line_smart(&format!("jns function_{helper}{result}"));
+    return builder.collapse_flat();
+}
+
+fn get_store_text(arg: Place, context: &CompilerContext) -> String {
+    return match arg {
+        Place::Variable(id) => format!(
+            "store {}",
+            get_var_text(context.tree.get_var(id), &context.tree)
+        ),
+        Place::Field(id, field) => {
+            let var = context.tree.get_var(id);
+            format!("store {}", get_field_text(var, field, &context.tree))
+        }
+        Place::PtrField(id, field) => format!(
+            "store var__field_value\n{}",
+            get_ptr_field_text(id, field, "load var__field_value\nstorei", context)
+        ),
+    };
+}
 fn get_set_from_arg(var: &Variable, arg: &ArgumentCallArg, context: &CompilerContext) -> String {
     let var_t = get_var_text(var, &context.tree);
     return format!("{}\nstore {var_t}", get_load_from_arg_text(arg, context));
 }
 
 fn get_set_var_to_num_text(var: &Variable, value: i32, tree: &ProgramTree) -> String {
-    let var_t = get_var_text(var, tree);
     let value_t = get_constant_text(value);
+    if let DataType::Struct(id) = var.data_type {
+        let fields = 0..tree.get_struct(id).fields.len();
+        return fields
+            .map(|e| format!("load {value_t}\nstore {}", get_field_text(var, e, tree)))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    let var_t = get_var_text(var, tree);
     return format!("load {value_t}\nstore {var_t}");
 }
 
@@ -89,36 +240,55 @@ fn get_set_var_to_other_text(var: &Variable, value: &Variable, tree: &ProgramTre
 
 pub fn compile_variables(context: &mut CompilerContext, count: usize) -> String {
     let mut builder = Builder::new();
-    //handling constants
-    //todo the whole handling of constants here is prety dirty
-    let mut all_constants = HashSet::new();
-    all_constants.extend(context.tree.constants_used.clone());
-    let v: Vec<i32> = context.address_map.iter().map(|e| *e.1 as i32).collect();
-    all_constants.extend(v);
-    let mut constants_vector: Vec<_> = all_constants.iter().collect();
-    constants_vector.sort();
-    for el in &constants_vector {
-        builder.push_line_smart(&constant_decl_text(**el));
-    }
 
     //global variables
     for el in &context.tree.globals {
+        let address = builder.count() + count;
         builder.push_line_smart(&var_decl_text(&el, &context.tree));
-        context
-            .address_map
-            .insert(el.id, builder.count() + count - 1);
+        context.address_map.insert(el.id, address);
     }
 
     //local variables
     for fnc in &context.tree.functions {
         for el in &fnc.locals {
+            let address = builder.count() + count;
             builder.push_line_smart(&var_decl_text(&el, &context.tree));
-            context
-                .address_map
-                .insert(el.id, builder.count() + count - 1);
+            context.address_map.insert(el.id, address);
+        }
+    }
+
+    //strings, first pointers to them then zero terminated content
+    let strings = &context.tree.strings_used;
+    let mut string_addr = builder.count() + count + strings.len();
+    for (i, el) in strings.iter().enumerate() {
+        builder.push_line_smart(&format!("{}, DEC {}", get_string_ptr_text(i), string_addr));
+        string_addr += el.chars().count() + 1;
+    }
+    for (i, el) in strings.iter().enumerate() {
+        let mut label = format!("str_{i},");
+        for chr in el.chars().chain(std::iter::once('\0')) {
+            builder.push_line_smart(&format!("{label} DEC {}", chr as u32));
+            label = "".to_owned();
         }
     }
 
+    //handling constants, after variables so addresses taken with & are known
+    //todo the whole handling of constants here is prety dirty
+    let mut all_constants = HashSet::new();
+    all_constants.extend(context.tree.constants_used.clone());
+    let v: Vec<i32> = context
+        .tree
+        .addresses_used
+        .iter()
+        .map(|e| context.address_map[e] as i32)
+        .collect();
+    all_constants.extend(v);
+    let mut constants_vector: Vec<_> = all_constants.iter().collect();
+    constants_vector.sort();
+    for el in &constants_vector {
+        builder.push_line_smart(&constant_decl_text(**el));
+    }
+
     return builder.collapse_flat();
 }
 
@@ -135,7 +305,9 @@ pub fn compile_simple(
             let fnc = context.tree.get_fnc(ret.fnc);
             return Some(get_function_return_text(fnc, ret.ret_val.as_ref(), context));
         }
-        Statement::Inline(inline) => Some(inline.clone().trim().into()),
+        Statement::Inline(parts) => Some(get_inline_text(parts, context)),
+        //flag label holds the address of the flag
+        Statement::Goto(id) => Some(format!("jumpi {}", get_flag_text(*id, &context.tree))),
         Statement::FunctionCall(call) => {
             let fnc = context.tree.get_fnc(call.fnc_id);
             match fnc.is_stack {
@@ -144,10 +316,10 @@ pub fn compile_simple(
             }
         }
         Statement::Assignment(ass) => {
-            return Some(get_set_from_arg(
-                context.tree.get_var(ass.left),
-                &ass.right,
-                &context,
+            return Some(format!(
+                "{}\n{}",
+                get_load_from_arg_text(&ass.right, context),
+                get_store_text(ass.left, context)
             ));
         }
         _ => None,
@@ -218,20 +390,15 @@ fn get_stack_function_call_text(
         //restore locals
         for arg in from.locals.iter().rev() {
             //reversed since stuck and stuff
-            let push_call = FunctionCall::new(pop_func, vec![], Some(arg.id), from_id);
+            let push_call =
+                FunctionCall::new(pop_func, vec![], Some(Place::Variable(arg.id)), from_id);
             builder.push_line_smart(&get_normal_function_call_text(&push_call, context));
         }
     }
     builder.push_line_smart("load var_return_saver\nstore var_return");
 
     if let Some(assignment) = master_call.assignment {
-        builder.push_line_smart(
-            format!(
-                "store {}",
-                get_var_text(context.tree.get_var(assignment), &context.tree)
-            )
-            .as_str(),
-        );
+        builder.push_line_smart(&get_store_text(assignment, context));
     }
     return builder.collapse_flat();
 }
@@ -261,10 +428,8 @@ pub fn compile_advance(
 ) -> Option<String> {
     match st {
         Statement::Flag(flag) => {
-            let fnc = context.tree.get_fnc(id);
-            context
-                .flags
-                .push((format!("{}_{}", fnc.name, flag.label), line));
+            let label = get_flag_text(flag.id, &context.tree);
+            context.flags.push((label, line));
             return Some("".to_owned());
         }
         Statement::If(If {
@@ -325,10 +490,115 @@ pub fn compile_advance(
             builder.push_line_smart(&format!("end_if_{},store var__temp", counter));
             return Some(builder.collapse_flat());
         }
+        Statement::Switch(switch) => return Some(get_switch_text(switch, context, line, id)),
         _ => None,
     }
 }
 
+///compiles arms after the dispatch code, returns (code, address of every case, address of default)
+fn get_switch_arms_text(
+    switch: &Switch,
+    counter: usize,
+    context: &mut CompilerContext,
+    line: usize,
+    id: FunctionId,
+) -> (String, Vec<usize>, usize) {
+    let mut builder = Builder::new();
+    let mut case_addresses = Vec::new();
+    for (k, (_, block)) in switch.cases.iter().enumerate() {
+        case_addresses.push(line + builder.count());
+        builder.push_line_smart(&format!("case_{counter}_{k},store var__temp"));
+        builder.push_line_smart(&compile_block(block, context, line + builder.count(), id));
+        builder.push_line_smart(&format!("jump end_switch_{counter}"));
+    }
+    let default_address = line + builder.count();
+    if let Some(block) = &switch.default {
+        builder.push_line_smart(&format!("default_{counter},store var__temp"));
+        builder.push_line_smart(&compile_block(block, context, line + builder.count(), id));
+    }
+    //without default the address points to end_switch
+    return (builder.collapse_flat(), case_addresses, default_address);
+}
+
+fn get_switch_text(
+    switch: &Switch,
+    context: &mut CompilerContext,
+    line: usize,
+    id: FunctionId,
+) -> String {
+    let counter = context.push_counter();
+    let mut builder = Builder::new();
+    let default = match switch.default {
+        Some(_) => format!("default_{counter}"),
+        None => format!("end_switch_{counter}"),
+    };
+
+    builder.push_line_smart(&get_load_from_arg_text(&switch.value, context));
+    match switch.table_range() {
+        Some((min, len)) => {
+            // temp = value-min, jumps through table[temp] when 0<=temp<len
+            builder.push_line_smart(&format!("subt {}", get_constant_text(min)));
+            builder.push_line_smart("store var__temp");
+            builder.push_line_smart("skipcond 000");
+            builder.push_line_smart(&format!("jump above_min_{counter}"));
+            builder.push_line_smart(&format!("jump {default}"));
+            builder.push_line_smart(&format!(
+                "above_min_{counter},subt {}",
+                get_constant_text(len)
+            ));
+            builder.push_line_smart("skipcond 000");
+            builder.push_line_smart(&format!("jump {default}"));
+            builder.push_line_smart("load var__temp");
+            builder.push_line_smart(&format!("add addr_{counter}"));
+            builder.push_line_smart("store var__temp");
+            builder.push_line_smart("loadi var__temp");
+            builder.push_line_smart("store var__temp");
+            builder.push_line_smart("jumpi var__temp");
+
+            let table_address = line + builder.count();
+            context.addrs.push((counter, table_address));
+            let arms_address = table_address + len as usize;
+            let (arms, case_addresses, default_address) =
+                get_switch_arms_text(switch, counter, context, arms_address, id);
+
+            for j in 0..len {
+                let address = switch
+                    .cases
+                    .iter()
+                    .position(|e| e.0 == min + j)
+                    .map(|k| case_addresses[k])
+                    .unwrap_or(default_address);
+                builder.push_line_smart(&format!("table_{counter}_{j}, dec {address}"));
+            }
+            builder.push_line_smart(&arms);
+        }
+        None => {
+            builder.push_line_smart("store var__temp");
+            for (k, (value, _)) in switch.cases.iter().enumerate() {
+                let test = format!("load var__temp\nsubt {}", get_constant_text(*value));
+                match k {
+                    0 => builder.push_line_smart(&test),
+                    _ => builder.push_line_smart(&format!("test_{counter}_{k},{test}")),
+                };
+                builder.push_line_smart("skipcond 400");
+                match k + 1 == switch.cases.len() {
+                    true => builder.push_line_smart(&format!("jump {default}")),
+                    false => builder.push_line_smart(&format!("jump test_{counter}_{}", k + 1)),
+                };
+                builder.push_line_smart(&format!("jump case_{counter}_{k}"));
+            }
+            if switch.cases.is_empty() {
+                builder.push_line_smart(&format!("jump {default}"));
+            }
+            let arms_address = line + builder.count();
+            let (arms, ..) = get_switch_arms_text(switch, counter, context, arms_address, id);
+            builder.push_line_smart(&arms);
+        }
+    }
+    builder.push_line_smart(&format!("end_switch_{counter},store var__temp"));
+    return builder.collapse_flat();
+}
+
 pub fn compile_function(fnc: FunctionId, context: &mut CompilerContext, line: usize) -> String {
     let fnc = context.tree.get_fnc(fnc);
     if fnc.content.is_none() {
@@ -355,7 +625,8 @@ pub fn compile_function(fnc: FunctionId, context: &mut CompilerContext, line: us
         //popping so reverse
         for arg in fnc.locals[0..fnc.args].iter().rev() {
             //we only pop arguments, other locals are fresh
-            let pop_call = FunctionCall::new(pop_fnc.id, vec![], Some(arg.id), fnc.id);
+            let pop_call =
+                FunctionCall::new(pop_fnc.id, vec![], Some(Place::Variable(arg.id)), fnc.id);
             builder.push_line_smart(&get_normal_function_call_text(&pop_call, context));
         }
     }
@@ -383,7 +654,10 @@ pub fn compile_function(fnc: FunctionId, context: &mut CompilerContext, line: us
             context,
         )); //TODO forgetting and getting function again bad
     }
-    return builder.collapse_flat();
+    let code = builder.collapse_flat();
+    let fnc_line = context.tree.get_fnc(id).line;
+    context.push_origin(line, &code, fnc_line, id);
+    return code;
 }
 pub fn get_function_return_text(
     fnc: &Function,
@@ -398,7 +672,7 @@ pub fn get_function_return_text(
             } else {
                 "".to_owned()
             },
-            store_op = get_store_text(context.tree.get_ret_var().id, context),
+            store_op = get_store_text(Place::Variable(context.tree.get_ret_var().id), context),
             fnc_name = get_real_fnc_name_text(fnc)
         );
     } else {
@@ -414,7 +688,7 @@ pub fn get_function_return_text(
 }
 
 pub fn compile_block(
-    block: &Vec<Statement>,
+    block: &Vec<LineStatement>,
     context: &mut CompilerContext,
     lines: usize,
     id: FunctionId,
@@ -422,15 +696,18 @@ pub fn compile_block(
     let tree = &context.tree;
     //do the code
     let mut builder = Builder::new();
-    for statement in block {
-        let simple_maybe = compile_simple(&statement, context, builder.count() + lines);
+    for LineStatement { statement, line } in block {
+        let address = builder.count() + lines;
+        let simple_maybe = compile_simple(statement, context, address);
 
         if let Some(simple) = simple_maybe {
+            context.push_origin(address, &simple, *line, id);
             builder.push_line_smart(&simple);
             continue;
         }
-        let adv_maybe = compile_advance(&statement, context, builder.count() + lines, id);
+        let adv_maybe = compile_advance(statement, context, address, id);
         if let Some(adv) = adv_maybe {
+            context.push_origin(address, &adv, *line, id);
             if adv != "" {
                 builder.push_line_smart(&adv);
             }
@@ -440,10 +717,119 @@ pub fn compile_block(
     return t;
 }
 
+///a*b, done with shift and add over 16 bits so it works for negative numbers too
+const MUL_HELPER: &str = "function__mul,DEC 0
+clear
+store var__mul_res
+load var__mul_bits
+store var__mul_counter
+_mul_loop,load var__mul_res
+add var__mul_res
+store var__mul_res
+load var__mul_b
+skipcond 000
+jump _mul_shift
+load var__mul_res
+add var__mul_a
+store var__mul_res
+_mul_shift,load var__mul_b
+add var__mul_b
+store var__mul_b
+load var__mul_counter
+subt var__mul_one
+store var__mul_counter
+skipcond 400
+jump _mul_loop
+load var__mul_res
+jumpi function__mul
+var__mul_a,DEC 0
+var__mul_b,DEC 0
+var__mul_res,DEC 0
+var__mul_counter,DEC 0
+var__mul_bits,DEC 16
+var__mul_one,DEC 1";
+
+///a/b (in acc) and a%b (in var__divmod_r) by repeated subtraction, rounding towards zero
+///x/0 gives 0 and x%0 gives x
+const DIVMOD_HELPER: &str = "function__divmod,DEC 0
+clear
+store var__divmod_q
+store var__divmod_q_neg
+store var__divmod_r_neg
+load var__divmod_a
+store var__divmod_r
+load var__divmod_b
+skipcond 400
+jump _divmod_sign_a
+jump _divmod_end
+_divmod_sign_a,load var__divmod_a
+skipcond 000
+jump _divmod_sign_b
+clear
+subt var__divmod_a
+store var__divmod_r
+load var__divmod_one
+store var__divmod_q_neg
+store var__divmod_r_neg
+_divmod_sign_b,load var__divmod_b
+skipcond 000
+jump _divmod_loop
+clear
+subt var__divmod_b
+store var__divmod_b
+load var__divmod_one
+subt var__divmod_q_neg
+store var__divmod_q_neg
+_divmod_loop,load var__divmod_r
+subt var__divmod_b
+skipcond 000
+jump _divmod_step
+jump _divmod_fix_q
+_divmod_step,store var__divmod_r
+load var__divmod_q
+add var__divmod_one
+store var__divmod_q
+jump _divmod_loop
+_divmod_fix_q,load var__divmod_q_neg
+skipcond 400
+jump _divmod_neg_q
+jump _divmod_fix_r
+_divmod_neg_q,clear
+subt var__divmod_q
+store var__divmod_q
+_divmod_fix_r,load var__divmod_r_neg
+skipcond 400
+jump _divmod_neg_r
+jump _divmod_end
+_divmod_neg_r,clear
+subt var__divmod_r
+store var__divmod_r
+_divmod_end,load var__divmod_q
+jumpi function__divmod
+var__divmod_a,DEC 0
+var__divmod_b,DEC 0
+var__divmod_q,DEC 0
+var__divmod_r,DEC 0
+var__divmod_q_neg,DEC 0
+var__divmod_r_neg,DEC 0
+var__divmod_one,DEC 1";
+
+///helpers for operators, only the ones used by the program
+fn compile_helpers(context: &CompilerContext) -> String {
+    let mut builder = Builder::new();
+    if context.tree.features.contains(FeatureFlags::Multiplication) {
+        builder.push_line_smart(MUL_HELPER);
+    }
+    if context.tree.features.contains(FeatureFlags::Division) {
+        builder.push_line_smart(DIVMOD_HELPER);
+    }
+    return builder.collapse_flat();
+}
+
 fn compile_lower_kind_variables(context: &CompilerContext) -> String {
     let mut builder = Builder::new();
     for el in &context.flags {
-        builder.push_line_smart(format!("flag_{}, dec {}", el.0, el.1).as_str());
+        builder.push_line_smart(format!("{}, dec {}", el.0, el.1).as_str());
     }
     for el in &context.addrs {
         let counter = el.0;
@@ -459,7 +845,8 @@ fn compile_lower_kind_variables(context: &CompilerContext) -> String {
 ///the tree outputed from "parser" as OK is always assumed to be program that's correct.
 ///Thats why this function never outpus an error.
 ///If this funciton panic it means either the tree was not from parser or parser has errors in its code
-pub fn compile(tree: ProgramTree) -> String {
+///Also tells where each part of the code comes from
+pub fn compile_with_origins(tree: ProgramTree) -> Compiled {
     let mut builder = Builder::new();
     let mut context = CompilerContext::default();
     context.tree = tree;
@@ -472,6 +859,13 @@ pub fn compile(tree: ProgramTree) -> String {
     for fnc in ids {
         builder.push_line_smart(&compile_function(fnc, &mut context, builder.count()));
     }
+    builder.push_line_smart(&compile_helpers(&context));
     builder.push_line_smart(&compile_lower_kind_variables(&context));
-    return builder.collapse_flat();
+    //nested statements are compiled before the ones containing them are done
+    context.origins.sort_by_key(|e| (e.from, usize::MAX - e.to));
+    return Compiled {
+        code: builder.collapse_flat(),
+        origins: context.origins,
+        address_map: context.address_map,
+    };
 }
@@ -1,57 +1,235 @@
 use crate::argument_parser::{self, ArgumentMapping};
+use crystal_marie::emulator::ValueMode;
 
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum Command {
+    #[default]
+    Build, //compile to marie
+    Run,   //compile and run on the emulator
+    Check, //only report errors and warnings
+    Fmt,   //format the source file
+    Debug, //run on the emulator with a source level debugger
+    Test,  //run programs with expected output
+}
+pub const COMMANDS: [(&str, Command); 6] = [
+    ("build", Command::Build),
+    ("run", Command::Run),
+    ("check", Command::Check),
+    ("fmt", Command::Fmt),
+    ("debug", Command::Debug),
+    ("test", Command::Test),
+];
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum EmitKind {
+    Tokens,
+    Ast,
+    Ir, //marie code with the address of each word
+    #[default]
+    Asm,
+    Bin,     //machine code as hex
+    Listing, //address, machine code and marie code of every word with its source line
+}
+const EMIT_KINDS: [(&str, EmitKind); 5] = [
+    ("tokens", EmitKind::Tokens),
+    ("ast", EmitKind::Ast),
+    ("ir", EmitKind::Ir),
+    ("asm", EmitKind::Asm),
+    ("bin", EmitKind::Bin),
+];
+
+#[derive(Clone)]
 pub enum OutputMethod {
     File(String),
     Stdout,
 }
 
+#[derive(PartialEq)]
+pub enum DiagnosticsFormat {
+    Text,
+    Json, //one object per line
+}
+
 pub struct Config {
+    pub command: Command,
     pub input_file_name: Option<String>,
-    pub output: OutputMethod,
+    pub output: Option<OutputMethod>, //none if not given, default depends on command
     pub lib: Vec<String>,
     pub help_mode: bool,
+    pub version_mode: bool,
+    pub emit: EmitKind,
+    pub value_mode: ValueMode, //io of "run"
+    pub max_steps: Option<usize>,
+    pub warnings_as_errors: bool,
+    pub max_errors: Option<usize>, //none prints all
+    pub diagnostics: DiagnosticsFormat,
+    pub source_comments: bool,
+    pub source_map: Option<Option<String>>, //some(none) writes it next to the output file
+    pub write: bool,                        //fmt rewrites the input file
 }
 impl Default for Config {
     fn default() -> Self {
         Self {
+            command: Command::Build,
             input_file_name: None,
-            output: OutputMethod::File("a.marie".to_owned()),
+            output: None,
             lib: Vec::new(),
             help_mode: false,
+            version_mode: false,
+            emit: EmitKind::Asm,
+            value_mode: ValueMode::Dec,
+            max_steps: None,
+            warnings_as_errors: false,
+            max_errors: None,
+            source_comments: false,
+            source_map: None,
+            diagnostics: DiagnosticsFormat::Text,
+            write: false,
         }
     }
 }
 
+fn parse_count(name: &str, e: &[String]) -> Result<usize, String> {
+    if e.len() != 1 {
+        return Err(format!("{name} argument takes exactly one value"));
+    }
+    return e[0]
+        .parse::<usize>()
+        .map_err(|_| format!("{name} expects a number, found {}", e[0]));
+}
+
+///"[command] file [options]", command is build if not given
 pub fn parse_arguments(args: &[String]) -> Result<Config, String> {
+    let command = args
+        .first()
+        .and_then(|e| COMMANDS.iter().find(|(name, _)| name == e))
+        .map(|e| e.1);
+    let args = if command.is_some() { &args[1..] } else { args };
+
     let parse_arguments = argument_parser::parse_arguments::<Config>(
         args,
-        || Config::default(),
+        || Config {
+            command: command.unwrap_or_default(),
+            ..Config::default()
+        },
         |e, c| {
             c.input_file_name = e.map(|e| e.to_owned());
             return Ok(());
         },
         vec![
+            ArgumentMapping::new('h', |_: &[String], c: &mut Config| {
+                c.help_mode = true;
+                return Ok(());
+            })
+            .with_long("help"),
+            ArgumentMapping::new('V', |_: &[String], c: &mut Config| {
+                c.version_mode = true;
+                return Ok(());
+            })
+            .with_long("version"),
             ArgumentMapping::new('l', |e: &[String], c: &mut Config| {
                 if e.len() < 1 {
                     return Err("after -l, specify at least one lib file".to_owned());
                 }
-                c.lib = e.iter().map(|e| e.clone()).collect();
+                c.lib.extend(e.iter().cloned());
                 return Ok(());
-            }),
+            })
+            .with_long("lib")
+            .repeatable(),
             ArgumentMapping::new('o', |e: &[String], c: &mut Config| {
                 if e.len() != 1 {
                     return Err("o argument takes exactly one value".to_owned());
                 }
-                c.output = OutputMethod::File(e[0].clone());
+                c.output = Some(OutputMethod::File(e[0].clone()));
                 return Ok(());
-            }),
+            })
+            .with_long("output"),
             ArgumentMapping::new('s', |e: &[String], c: &mut Config| {
                 if e.len() != 0 {
                     return Err("s argument takes no values ".to_owned());
                 }
-                c.output = OutputMethod::Stdout;
+                c.output = Some(OutputMethod::Stdout);
+                return Ok(());
+            })
+            .with_long("stdout"),
+            ArgumentMapping::new('i', |e: &[String], c: &mut Config| {
+                if !e.is_empty() {
+                    return Err("i argument takes no values".to_owned());
+                }
+                c.write = true;
+                return Ok(());
+            })
+            .with_long("write"),
+            ArgumentMapping::long_only("emit", |e: &[String], c: &mut Config| {
+                c.emit = match e {
+                    [kind] => EMIT_KINDS.iter().find(|e| e.0 == kind).map(|e| e.1),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    "emit argument takes one value: tokens, ast, ir, asm or bin".to_owned()
+                })?;
                 return Ok(());
             }),
+            ArgumentMapping::long_only("listing", |e: &[String], c: &mut Config| {
+                if e.len() != 0 {
+                    return Err("listing argument takes no values".to_owned());
+                }
+                c.emit = EmitKind::Listing;
+                return Ok(());
+            }),
+            ArgumentMapping::long_only("source-comments", |e: &[String], c: &mut Config| {
+                if e.len() != 0 {
+                    return Err("source-comments argument takes no values".to_owned());
+                }
+                c.source_comments = true;
+                return Ok(());
+            }),
+            ArgumentMapping::long_only("source-map", |e: &[String], c: &mut Config| {
+                c.source_map = match e {
+                    [] => Some(None),
+                    [file] => Some(Some(file.clone())),
+                    _ => return Err("source-map argument takes at most one value".to_owned()),
+                };
+                return Ok(());
+            }),
+            ArgumentMapping::long_only("io-mode", |e: &[String], c: &mut Config| {
+                c.value_mode = match e {
+                    [kind] if kind == "dec" => ValueMode::Dec,
+                    [kind] if kind == "hex" => ValueMode::Hex,
+                    [kind] if kind == "ascii" => ValueMode::Ascii,
+                    _ => {
+                        return Err("io-mode argument takes one value: dec, hex or ascii".to_owned())
+                    }
+                };
+                return Ok(());
+            }),
+            ArgumentMapping::long_only("max-steps", |e: &[String], c: &mut Config| {
+                c.max_steps = Some(parse_count("max-steps", e)?);
+                return Ok(());
+            }),
+            ArgumentMapping::new('W', |e: &[String], c: &mut Config| {
+                if e.len() != 1 || e[0] != "error" {
+                    return Err("W argument takes exactly one value: error".to_owned());
+                }
+                c.warnings_as_errors = true;
+                return Ok(());
+            }),
+            ArgumentMapping::new('e', |e: &[String], c: &mut Config| {
+                c.max_errors = Some(parse_count("max-errors", e)?);
+                return Ok(());
+            })
+            .with_long("max-errors"),
+            ArgumentMapping::new('d', |e: &[String], c: &mut Config| {
+                c.diagnostics = match e {
+                    [kind] if kind == "text" => DiagnosticsFormat::Text,
+                    [kind] if kind == "json" => DiagnosticsFormat::Json,
+                    _ => {
+                        return Err("diagnostics argument takes one value: text or json".to_owned())
+                    }
+                };
+                return Ok(());
+            })
+            .with_long("diagnostics"),
         ],
     );
     return parse_arguments;
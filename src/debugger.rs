@@ -0,0 +1,415 @@
+use crate::{
+    compiler::find_function_range,
+    emulator::{Io, Machine, StopReason},
+    parser::{DataType, FunctionId, Variable},
+    source_map::{get_ranges, SourceRange},
+    Output,
+};
+
+const DEBUG_HELP: &str = "\
+break <line|file:line|function>  set a breakpoint (b), lines of the main file by default
+delete [n]                       remove breakpoint n or all of them
+breakpoints                      list breakpoints
+continue                         run until a breakpoint or the end (c)
+step                             run to the next source line, entering calls (s)
+next                             run to the next source line of this function (n)
+finish                           run until the current function returns
+stepi                            execute one instruction (si)
+backtrace                        show the call stack (bt)
+where                            show the current line (w)
+print <name>                     value of a local of the current function or of a global (p)
+locals                           locals of the current function
+globals                          all globals
+x <address> [count]              dump memory, address is decimal or 0x hex
+stack [count]                    dump memory below stack_ptr
+restart                          start the program again
+quit                             stop debugging (q)
+locals of stack functions are saved on the stack while they call others,
+print shows the values of the innermost call";
+const STACK_DUMP_SIZE: usize = 16;
+
+//for instructions that don't do io
+struct NoIo;
+impl Io for NoIo {
+    fn input(&mut self) -> Option<u16> {
+        return None;
+    }
+    fn output(&mut self, _: u16) {}
+}
+
+///function being executed, its caller goes on at return_address
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub(crate) fnc: FunctionId,
+    pub return_address: usize,
+}
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub address: usize,
+    pub description: String,
+}
+
+///runs compiled program on the emulator while tracking source lines and calls
+pub struct Debugger<'a> {
+    output: &'a Output,
+    program: Vec<u16>,
+    ranges: Vec<SourceRange>,
+    functions: Vec<(FunctionId, usize)>, //address of the function label, calls jump right after it
+    pub machine: Machine,
+    pub frames: Vec<Frame>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub finished: Option<StopReason>,
+    max_steps: Option<usize>, //for each command that runs the program
+}
+impl<'a> Debugger<'a> {
+    pub fn new(output: &'a Output, program: &[u16], max_steps: Option<usize>) -> Self {
+        let functions = output
+            .tree
+            .functions
+            .iter()
+            .filter_map(|e| find_function_range(&output.origins, e.id).map(|r| (e.id, r.0)))
+            .collect();
+        let mut debugger = Self {
+            output,
+            program: program.to_vec(),
+            ranges: get_ranges(output),
+            functions,
+            machine: Machine::new(program),
+            frames: Vec::new(),
+            breakpoints: Vec::new(),
+            finished: None,
+            max_steps,
+        };
+        debugger.restart();
+        return debugger;
+    }
+    ///back to the beginning of main, breakpoints stay
+    pub fn restart(&mut self) {
+        self.machine = Machine::new(&self.program);
+        self.frames.clear();
+        self.finished = None;
+        //first instruction only calls main
+        self.step_instruction(&mut NoIo);
+    }
+
+    fn find_range(&self, address: usize) -> Option<&SourceRange> {
+        return self
+            .ranges
+            .iter()
+            .find(|e| e.from <= address && address < e.to);
+    }
+    fn file_name(&self, file: usize) -> &str {
+        return self
+            .output
+            .sources
+            .files
+            .get(file)
+            .map_or("", |e| e.as_str());
+    }
+    ///code of line of file, without comment
+    fn source_text(&self, file: usize, line: usize) -> &str {
+        let code = &self.output.sources.code;
+        return code
+            .origins
+            .iter()
+            .position(|e| e.file == file && e.line == line)
+            .and_then(|e| code.get_line(e))
+            .map_or("", |e| e.split("//").next().unwrap().trim());
+    }
+    fn location_text(&self, address: usize) -> String {
+        return match self.find_range(address) {
+            Some(range) => format!(
+                "{} at {}:{}  {}",
+                range.function,
+                self.file_name(range.file),
+                range.line + 1,
+                self.source_text(range.file, range.line)
+            ),
+            None => format!("address {address:03X}, no source"),
+        };
+    }
+    fn pc(&self) -> usize {
+        return self.machine.pc as usize;
+    }
+    ///file and line of the current instruction
+    fn current_line(&self) -> Option<(usize, usize)> {
+        return self.find_range(self.pc()).map(|e| (e.file, e.line));
+    }
+
+    ///executes one instruction, keeps frames up to date
+    fn step_instruction(&mut self, io: &mut dyn Io) -> Option<StopReason> {
+        let pc = self.pc();
+        let word = self.machine.memory[pc];
+        let called = match word >> 12 {
+            0x0 => self
+                .functions
+                .iter()
+                .find(|e| e.1 == (word & 0xFFF) as usize)
+                .map(|e| e.0),
+            _ => None,
+        };
+        if let Some(reason) = self.machine.step(io) {
+            return Some(reason);
+        }
+        match called {
+            Some(fnc) => self.frames.push(Frame {
+                fnc,
+                return_address: pc + 1,
+            }),
+            //both normal and stack functions return right after the call
+            None if self
+                .frames
+                .last()
+                .is_some_and(|e| e.return_address == self.pc()) =>
+            {
+                self.frames.pop();
+            }
+            None => {}
+        }
+        return None;
+    }
+    ///runs until stop says so, a breakpoint is hit or the program ends
+    fn run_until(&mut self, io: &mut dyn Io, stop: impl Fn(&Self) -> bool) -> String {
+        if let Some(reason) = self.finished {
+            return format!(
+                "program is not running ({}), use restart",
+                stop_text(reason)
+            );
+        }
+        let limit = self.max_steps.map(|e| self.machine.steps + e);
+        loop {
+            if limit.is_some_and(|e| self.machine.steps >= e) {
+                return format!(
+                    "stopped after {} steps\n{}",
+                    self.machine.steps,
+                    self.location_text(self.pc())
+                );
+            }
+            if let Some(reason) = self.step_instruction(io) {
+                if reason != StopReason::InputEnded {
+                    self.finished = Some(reason);
+                }
+                return stop_text(reason).to_owned();
+            }
+            let pc = self.pc();
+            if let Some(i) = self.breakpoints.iter().position(|e| e.address == pc) {
+                return format!("breakpoint {i}, {}", self.location_text(pc));
+            }
+            if stop(self) {
+                return self.location_text(pc);
+            }
+        }
+    }
+
+    fn find_breakpoint_address(&self, place: &str) -> Result<(usize, String), String> {
+        let (file, line) = match place.rsplit_once(':') {
+            Some((file, line)) => {
+                let index = self
+                    .output
+                    .sources
+                    .files
+                    .iter()
+                    .position(|e| e == file || e.ends_with(&format!("/{file}")))
+                    .ok_or_else(|| format!("file {file} not found"))?;
+                (index, line)
+            }
+            None => (0, place),
+        };
+        if let Ok(line) = line.parse::<usize>() {
+            let address = self
+                .ranges
+                .iter()
+                .filter(|e| e.file == file && e.line + 1 == line)
+                .map(|e| e.from)
+                .min()
+                .ok_or_else(|| format!("no code at line {line}"))?;
+            return Ok((address, format!("{}:{line}", self.file_name(file))));
+        }
+        let fnc = self
+            .output
+            .tree
+            .find_fnc_with_name(place)
+            .ok_or_else(|| format!("{place} is neither a line nor a function"))?;
+        let address = self
+            .functions
+            .iter()
+            .find(|e| e.0 == fnc.id)
+            .ok_or_else(|| format!("function {place} has no code"))?
+            .1;
+        return Ok((address + 1, format!("function {place}")));
+    }
+
+    fn value_text(&self, address: usize) -> String {
+        let value = self.machine.memory[address];
+        return format!("{} (0x{value:04X})", value as i16);
+    }
+    fn variable_text(&self, var: &Variable) -> String {
+        let tree = &self.output.tree;
+        let address = self.output.address_map[&var.id];
+        if let DataType::Struct(id) = var.data_type {
+            let fields: Vec<_> = tree
+                .get_struct(id)
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, e)| format!("{e}: {}", self.value_text(address + i)))
+                .collect();
+            return format!("{} = {{ {} }}", var.name, fields.join(", "));
+        }
+        return format!("{} = {}", var.name, self.value_text(address));
+    }
+    fn current_function_locals(&self) -> &[Variable] {
+        return match self.frames.last() {
+            Some(frame) => &self.output.tree.get_fnc(frame.fnc).locals,
+            None => &[],
+        };
+    }
+    fn memory_text(&self, from: usize, count: usize, mark: Option<usize>) -> String {
+        return (from..(from + count).min(self.machine.memory.len()))
+            .map(|e| {
+                let arrow = if Some(e) == mark {
+                    "  <- stack_ptr"
+                } else {
+                    ""
+                };
+                format!("{e:03X}: {}{arrow}", self.value_text(e))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    fn stack_text(&self, count: usize) -> Result<String, String> {
+        let tree = &self.output.tree;
+        let ptr = tree
+            .find_global_var_with_name("stack_ptr")
+            .map_err(|_| "program has no stack_ptr, is std included?".to_owned())?;
+        let ptr = self.machine.memory[self.output.address_map[&ptr.id]] as usize & 0xFFF;
+        let begin = tree
+            .find_named_constant("STACK_BEGIN")
+            .map_or(0, |e| e.value as usize);
+        let from = ptr.saturating_sub(count).max(begin.min(ptr));
+        return Ok(self.memory_text(from, ptr - from + 1, Some(ptr)));
+    }
+    fn backtrace_text(&self) -> String {
+        let mut lines = Vec::new();
+        for (i, frame) in self.frames.iter().enumerate().rev() {
+            //outer frames are at the call of the inner one
+            let address = match self.frames.get(i + 1) {
+                Some(inner) => inner.return_address - 1,
+                None => self.pc(),
+            };
+            let name = &self.output.tree.get_fnc(frame.fnc).name;
+            let location = self.find_range(address).map_or(String::new(), |e| {
+                format!(" at {}:{}", self.file_name(e.file), e.line + 1)
+            });
+            lines.push(format!("#{} {name}{location}", self.frames.len() - 1 - i));
+        }
+        return lines.join("\n");
+    }
+
+    ///executes one command, returns what should be shown
+    pub fn execute(&mut self, command: &str, io: &mut dyn Io) -> Result<String, String> {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        let count_arg = |i: usize, default: usize| -> Result<usize, String> {
+            return parts.get(i).map_or(Ok(default), |e| parse_address(e));
+        };
+        let depth = self.frames.len();
+        let line = self.current_line();
+        return match parts.as_slice() {
+            [] => Ok(String::new()),
+            ["help" | "h"] => Ok(DEBUG_HELP.to_owned()),
+            ["break" | "b", place] => {
+                let (address, description) = self.find_breakpoint_address(place)?;
+                self.breakpoints.push(Breakpoint {
+                    address,
+                    description,
+                });
+                Ok(format!(
+                    "breakpoint {} at {}",
+                    self.breakpoints.len() - 1,
+                    self.location_text(address)
+                ))
+            }
+            ["delete"] => {
+                self.breakpoints.clear();
+                Ok("all breakpoints deleted".to_owned())
+            }
+            ["delete", n] => {
+                let n = n
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|e| *e < self.breakpoints.len());
+                let n = n.ok_or_else(|| "no such breakpoint".to_owned())?;
+                self.breakpoints.remove(n);
+                Ok(format!("breakpoint {n} deleted"))
+            }
+            ["breakpoints"] => Ok(self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(i, e)| format!("{i}: {} ({:03X})", e.description, e.address))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ["continue" | "c"] => Ok(self.run_until(io, |_| false)),
+            ["step" | "s"] => Ok(self.run_until(io, |d| {
+                d.current_line().is_some() && (d.current_line() != line || d.frames.len() != depth)
+            })),
+            ["next" | "n"] => Ok(self.run_until(io, |d| {
+                d.frames.len() < depth
+                    || (d.frames.len() == depth
+                        && d.current_line().is_some()
+                        && d.current_line() != line)
+            })),
+            ["finish"] => Ok(self.run_until(io, |d| d.frames.len() < depth)),
+            ["stepi" | "si"] => Ok(self.run_until(io, |_| true)),
+            ["backtrace" | "bt"] => Ok(self.backtrace_text()),
+            ["where" | "w"] => Ok(self.location_text(self.pc())),
+            ["print" | "p", name] => {
+                let var = self
+                    .current_function_locals()
+                    .iter()
+                    .find(|e| e.name == *name)
+                    .or_else(|| self.output.tree.find_global_var_with_name(name).ok())
+                    .ok_or_else(|| format!("{name} is neither a local nor a global"))?;
+                Ok(self.variable_text(var))
+            }
+            ["locals"] => Ok(self
+                .current_function_locals()
+                .iter()
+                .map(|e| self.variable_text(e))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ["globals"] => Ok(self
+                .output
+                .tree
+                .globals
+                .iter()
+                .map(|e| self.variable_text(e))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            ["x", address, ..] => {
+                Ok(self.memory_text(parse_address(address)?, count_arg(2, 1)?, None))
+            }
+            ["stack", ..] => self.stack_text(count_arg(1, STACK_DUMP_SIZE)?),
+            ["restart"] => {
+                self.restart();
+                Ok(self.location_text(self.pc()))
+            }
+            _ => Err(format!("unknown command {command}, try help")),
+        };
+    }
+}
+
+fn stop_text(reason: StopReason) -> &'static str {
+    return match reason {
+        StopReason::Halt => "program halted",
+        StopReason::StepLimit => "step limit reached",
+        StopReason::InputEnded => "program asked for input, but it ended",
+    };
+}
+fn parse_address(t: &str) -> Result<usize, String> {
+    let value = match t.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => t.parse::<usize>(),
+    };
+    return value.map_err(|_| format!("{t} is not a number"));
+}
@@ -0,0 +1,109 @@
+use crate::{lib_handler::MergedCode, parser::ParserError, warnings::Warning, SourceFiles};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+impl Severity {
+    pub fn name(self) -> &'static str {
+        return match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+    }
+}
+pub const PARSE_ERROR: &str = "parse-error"; //any other syntax error
+pub const TYPE_MISMATCH: &str = "type-mismatch"; //value given to variable or argument
+pub const INVALID_DEREFERENCE: &str = "invalid-dereference";
+pub const OPERAND_TYPE: &str = "operand-type"; //non int value in an operation
+pub const UNDEFINED_VARIABLE: &str = "undefined-variable";
+pub const UNDEFINED_FUNCTION: &str = "undefined-function";
+pub const UNDEFINED_FLAG: &str = "undefined-flag";
+pub const UNDEFINED_STRUCT: &str = "undefined-struct";
+pub const UNDEFINED_FIELD: &str = "undefined-field";
+pub const DUPLICATE_FLAG: &str = "duplicate-flag";
+pub const DUPLICATE_DEFINITION: &str = "duplicate-definition"; //function, struct, field or constant
+pub const READ_ONLY_ARGUMENT: &str = "read-only-argument";
+pub const ARGUMENT_COUNT: &str = "argument-count";
+pub const MIXED_SEPARATORS: &str = "mixed-separators";
+pub const MISSING_PARENTHESES: &str = "missing-parentheses";
+pub const UNCLOSED_STRING: &str = "unclosed-string";
+pub const UNCLOSED_COMMENT: &str = "unclosed-comment";
+pub const INVALID_INLINE: &str = "invalid-inline-assembly";
+
+///error or warning of the compilation, line is in merged code
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub line: usize,
+    pub message: String,
+    #[new(default)]
+    pub columns: Option<(usize, usize)>, //from 0, end exclusive, whole code of the line if none
+}
+impl Diagnostic {
+    ///code is used if the error doesn't have its own
+    pub(crate) fn from_error(err: ParserError, code: &'static str) -> Self {
+        let mut diagnostic = Self::new(
+            Severity::Error,
+            err.code.unwrap_or(code),
+            err.line,
+            err.content,
+        );
+        diagnostic.columns = err.columns;
+        return diagnostic;
+    }
+    ///with -W error warnings come as errors
+    pub(crate) fn from_warning(warning: Warning, as_error: bool) -> Self {
+        let severity = if as_error {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        return Self::new(severity, warning.kind.code(), warning.line, warning.content);
+    }
+    ///one line json object, lines and columns start from 1,
+    ///columns span what the diagnostic is about (end is exclusive)
+    pub fn to_json(&self, sources: &SourceFiles) -> String {
+        let (file, line) = sources.get_origin(self.line);
+        let (column_start, column_end) = get_columns(self, &sources.code);
+        return format!(
+            "{{\"file\":\"{}\",\"line\":{},\"column_start\":{},\"column_end\":{},\"severity\":\"{}\",\"code\":\"{}\",\"message\":\"{}\"}}",
+            escape_json(file),
+            line + 1,
+            column_start,
+            column_end,
+            self.severity.name(),
+            self.code,
+            escape_json(&self.message)
+        );
+    }
+}
+
+pub fn escape_json(t: &str) -> String {
+    let mut escaped = String::with_capacity(t.len());
+    for c in t.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+///columns the diagnostic points at, from 1 with exclusive end
+pub fn get_columns(diagnostic: &Diagnostic, code: &MergedCode) -> (usize, usize) {
+    if let Some((start, end)) = diagnostic.columns {
+        return (start + 1, end + 1);
+    }
+    let text = code.get_line(diagnostic.line).unwrap_or("");
+    let text = text.split("//").next().unwrap();
+    let column_start = text.chars().take_while(|c| c.is_whitespace()).count() + 1;
+    let column_end = text.trim_end().chars().count() + 1;
+    return (column_start, column_end.max(column_start));
+}
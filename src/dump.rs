@@ -0,0 +1,294 @@
+use crate::{
+    assembler::Assembled,
+    lexer::{ConditionKind, TokenLine},
+    lib_handler::MergedCode,
+    parse_source,
+    parser::{
+        ArgumentCallArg, Block, DataType, InlinePart, OperationKind, Place, ProgramTree, Statement,
+        Variable, VariableId,
+    },
+    tokenize_source,
+    type_checker::get_type_name,
+    Diagnostics, Source,
+};
+
+const INDENT: &str = "    ";
+
+///"file:line" of a line of merged code, line starts from 1
+fn location_text(line: usize, code: &MergedCode, files: &[String]) -> String {
+    let origin = code.get_origin(line);
+    let file = files.get(origin.file).map_or("", |e| e.as_str());
+    return format!("{}:{}", file, origin.line + 1);
+}
+
+///one token line per line, with the place it comes from
+fn tokens_text(tokens: &[TokenLine], code: &MergedCode, files: &[String]) -> String {
+    let mut out = String::new();
+    for line in tokens {
+        out.push_str(&format!(
+            "{}: {:?}\n",
+            location_text(line.line_number, code, files),
+            line.elements
+        ));
+    }
+    return out.trim_end().to_owned();
+}
+
+fn field_name(var: &Variable, field: usize, tree: &ProgramTree) -> String {
+    return match var.data_type {
+        DataType::Struct(id) | DataType::StructPtr(id) => tree.get_struct(id).fields[field].clone(),
+        _ => format!("#{field}"),
+    };
+}
+fn var_text(id: VariableId, tree: &ProgramTree) -> String {
+    return tree.get_var(id).name.clone();
+}
+fn place_text(place: &Place, tree: &ProgramTree) -> String {
+    return match place {
+        Place::Variable(id) => var_text(*id, tree),
+        Place::Field(id, field) => {
+            let var = tree.get_var(*id);
+            format!("{}.{}", var.name, field_name(var, *field, tree))
+        }
+        Place::PtrField(id, field) => {
+            let var = tree.get_var(*id);
+            format!("{}->{}", var.name, field_name(var, *field, tree))
+        }
+    };
+}
+fn arg_text(arg: &ArgumentCallArg, tree: &ProgramTree) -> String {
+    return match arg {
+        ArgumentCallArg::Literal(n) => n.to_string(),
+        ArgumentCallArg::Reference(id) => var_text(*id, tree),
+        ArgumentCallArg::Deref(id) => format!("*{}", var_text(*id, tree)),
+        ArgumentCallArg::GetAddress(id) => format!("&{}", var_text(*id, tree)),
+        ArgumentCallArg::Flag(id) => format!("@{}", tree.get_flag_name(*id)),
+        ArgumentCallArg::Str(t) => format!("{t:?}"),
+        ArgumentCallArg::Operation(op) => {
+            let symbol = match op.kind {
+                OperationKind::Mul => "*",
+                OperationKind::Div => "/",
+                OperationKind::Mod => "%",
+            };
+            format!(
+                "({} {} {})",
+                arg_text(&op.a, tree),
+                symbol,
+                arg_text(&op.b, tree)
+            )
+        }
+        ArgumentCallArg::Field(id, field) => place_text(&Place::Field(*id, *field), tree),
+        ArgumentCallArg::PtrField(id, field) => place_text(&Place::PtrField(*id, *field), tree),
+    };
+}
+fn inline_text(parts: &[InlinePart], tree: &ProgramTree) -> String {
+    return parts
+        .iter()
+        .map(|part| match part {
+            InlinePart::Text(t) => t.clone(),
+            InlinePart::Variable(id) => format!("{{{}}}", var_text(*id, tree)),
+            InlinePart::Field(id, field) => {
+                format!("{{{}}}", place_text(&Place::Field(*id, *field), tree))
+            }
+            InlinePart::Constant(n) => n.to_string(),
+            InlinePart::Flag(id) => format!("{{@{}}}", tree.get_flag_name(*id)),
+        })
+        .collect();
+}
+fn condition_text(cond: ConditionKind) -> &'static str {
+    return match cond {
+        ConditionKind::Eq => "==",
+        ConditionKind::Less => "<",
+        ConditionKind::More => ">",
+    };
+}
+fn dump_block(block: &Block, depth: usize, ctx: &mut DumpContext) {
+    for line_statement in block {
+        let location = location_text(line_statement.line, ctx.code, ctx.files);
+        let tree = ctx.tree;
+        let text = match &line_statement.statement {
+            Statement::Inline(parts) => format!("inline {:?}", inline_text(parts, tree)),
+            Statement::If(s) => format!(
+                "if {} {} {}",
+                arg_text(&s.a, tree),
+                condition_text(s.cond),
+                arg_text(&s.b, tree)
+            ),
+            Statement::FunctionCall(call) => {
+                let args: Vec<_> = call.arguments.iter().map(|e| arg_text(e, tree)).collect();
+                let assignment = call
+                    .assignment
+                    .map_or(String::new(), |e| format!("{} = ", place_text(&e, tree)));
+                format!(
+                    "{}{}({})",
+                    assignment,
+                    tree.get_fnc(call.fnc_id).name,
+                    args.join(" ")
+                )
+            }
+            Statement::Flag(flag) => format!("@{}:", tree.get_flag_name(flag.id)),
+            Statement::Goto(id) => format!("goto @{}", tree.get_flag_name(*id)),
+            Statement::Switch(s) => format!("switch {}", arg_text(&s.value, tree)),
+            Statement::Ret(ret) => match &ret.ret_val {
+                Some(value) => format!("ret {}", arg_text(value, tree)),
+                None => "ret".to_owned(),
+            },
+            Statement::Assignment(a) => {
+                format!(
+                    "{} = {}",
+                    place_text(&a.left, tree),
+                    arg_text(&a.right, tree)
+                )
+            }
+        };
+        ctx.push(depth, &text, Some(location));
+
+        //nested blocks
+        match &line_statement.statement {
+            Statement::If(s) => {
+                dump_block(&s.if_true, depth + 1, ctx);
+                if let Some(if_false) = &s.if_false {
+                    ctx.push(depth, "else", None);
+                    dump_block(if_false, depth + 1, ctx);
+                }
+            }
+            Statement::Switch(s) => {
+                for (value, block) in &s.cases {
+                    ctx.push(depth, &format!("case {value}"), None);
+                    dump_block(block, depth + 1, ctx);
+                }
+                if let Some(default) = &s.default {
+                    ctx.push(depth, "default", None);
+                    dump_block(default, depth + 1, ctx);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct DumpContext<'a> {
+    tree: &'a ProgramTree,
+    code: &'a MergedCode,
+    files: &'a [String],
+    out: String,
+}
+impl DumpContext<'_> {
+    ///text at depth, location goes to the end of the line
+    fn push(&mut self, depth: usize, text: &str, location: Option<String>) {
+        self.out.push_str(&INDENT.repeat(depth));
+        self.out.push_str(text);
+        if let Some(location) = location {
+            self.out.push_str(&format!("  // {location}"));
+        }
+        self.out.push('\n');
+    }
+}
+
+fn variable_text(var: &Variable, tree: &ProgramTree) -> String {
+    let read_only = if var.read_only { " read only" } else { "" };
+    return format!(
+        "{} {:?}: {} = {}{}",
+        var.name,
+        var.id,
+        get_type_name(var.data_type, tree),
+        var.default_value,
+        read_only
+    );
+}
+///structs, globals and functions with their locals and statements
+fn ast_text(tree: &ProgramTree, code: &MergedCode, files: &[String]) -> String {
+    let mut ctx = DumpContext {
+        tree,
+        code,
+        files,
+        out: String::new(),
+    };
+    ctx.push(0, &format!("features: {:?}", tree.features), None);
+    for s in &tree.structs {
+        ctx.push(
+            0,
+            &format!("struct {} {}", s.name, s.fields.join(" ")),
+            None,
+        );
+    }
+    for c in &tree.named_constants {
+        ctx.push(0, &format!("const {} = {}", c.name, c.value), None);
+    }
+    for (i, t) in tree.strings_used.iter().enumerate() {
+        ctx.push(0, &format!("string {i} = {t:?}"), None);
+    }
+    let mut constants: Vec<_> = tree.constants_used.iter().collect();
+    constants.sort();
+    ctx.push(0, &format!("constants used: {constants:?}"), None);
+    for var in &tree.globals {
+        let location = location_text(var.line, code, files);
+        ctx.push(
+            0,
+            &format!("global {}", variable_text(var, tree)),
+            Some(location),
+        );
+    }
+    for fnc in &tree.functions {
+        let kind = if fnc.is_stack {
+            "function stack"
+        } else {
+            "function"
+        };
+        let location = location_text(fnc.line, code, files);
+        ctx.push(0, "", None);
+        ctx.push(
+            0,
+            &format!("{kind} {} {:?}", fnc.name, fnc.id),
+            Some(location),
+        );
+        for (i, var) in fnc.locals.iter().enumerate() {
+            let kind = if i < fnc.args { "arg" } else { "local" };
+            ctx.push(1, &format!("{kind} {}", variable_text(var, tree)), None);
+        }
+        if !fnc.flags.is_empty() {
+            ctx.push(1, &format!("flags: {}", fnc.flags.join(" ")), None);
+        }
+        match &fnc.content {
+            Some(block) => dump_block(block, 1, &mut ctx),
+            None => ctx.push(1, "no body", None),
+        }
+    }
+    return ctx.out.trim_end().to_owned();
+}
+///tokens of the sources, they are written before parsing so they can be looked at when it fails
+pub fn dump_tokens(sources: &[Source]) -> Result<String, Diagnostics> {
+    let files = tokenize_source(sources)?;
+    return Ok(tokens_text(&files.tokens, &files.code, &files.files));
+}
+///parsed program of the sources, before type checking and code generation
+pub fn dump_ast(sources: &[Source]) -> Result<String, Diagnostics> {
+    let files = tokenize_source(sources)?;
+    return match parse_source(&files) {
+        Ok(tree) => Ok(ast_text(&tree, &files.code, &files.files)),
+        Err(list) => Err(Diagnostics {
+            list,
+            sources: files,
+        }),
+    };
+}
+
+///address of every word with the marie line it comes from
+pub fn dump_ir(assembled: &Assembled, code: &str) -> String {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut out = String::new();
+    for (address, line) in assembled.lines.iter().enumerate() {
+        let text = lines[*line].split('/').next().unwrap().trim();
+        out.push_str(&format!("{address:03X}  {text}\n"));
+    }
+    return out.trim_end().to_owned();
+}
+///machine code as hex, one word per line
+pub fn dump_bin(assembled: &Assembled) -> String {
+    return assembled
+        .memory
+        .iter()
+        .map(|e| format!("{e:04X}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Write},
+};
+
+use crate::assembler::MEMORY_SIZE;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StopReason {
+    Halt,
+    StepLimit,
+    InputEnded, //program asked for input there wasn't
+}
+///how numbers are read and shown, like marie.js input/output modes
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ValueMode {
+    #[default]
+    Dec,
+    Hex,
+    Ascii,
+}
+impl ValueMode {
+    ///word of input as a value, ascii takes its first char
+    pub fn parse(self, word: &str) -> Option<u16> {
+        let value = match self {
+            ValueMode::Dec => word.parse::<i32>().ok()?,
+            ValueMode::Hex => i32::from_str_radix(word, 16).ok()?,
+            ValueMode::Ascii => word.chars().next()? as i32,
+        };
+        return Some(value as u16);
+    }
+    pub fn format(self, value: u16) -> String {
+        return match self {
+            ValueMode::Dec => (value as i16).to_string(),
+            ValueMode::Hex => format!("{:04X}", value),
+            ValueMode::Ascii => char::from_u32((value & 0xFF) as u32)
+                .unwrap_or('?')
+                .to_string(),
+        };
+    }
+}
+
+pub trait Io {
+    fn input(&mut self) -> Option<u16>;
+    fn output(&mut self, value: u16);
+}
+///reads stdin when program asks for input, writes every output right away
+#[derive(derive_new::new)]
+pub struct StdIo {
+    mode: ValueMode,
+    #[new(default)]
+    pending: VecDeque<String>, //read but not used words of stdin
+    #[new(default)]
+    pub prompt: bool, //"input: " is printed before reading, when stdin is shared with a debugger
+}
+impl StdIo {
+    fn read_line(&self) -> Option<String> {
+        if self.prompt {
+            print!("input: ");
+            let _ = io::stdout().flush();
+        }
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        return Some(line);
+    }
+}
+impl Io for StdIo {
+    fn input(&mut self) -> Option<u16> {
+        if self.mode == ValueMode::Ascii {
+            if self.pending.is_empty() {
+                let line = self.read_line()?;
+                self.pending.extend(line.chars().map(|e| e.to_string()));
+            }
+            return self.pending.pop_front()?.chars().next().map(|e| e as u16);
+        }
+        loop {
+            while self.pending.is_empty() {
+                let line = self.read_line()?;
+                self.pending
+                    .extend(line.split_whitespace().map(|e| e.to_owned()));
+            }
+            let word = self.pending.pop_front().unwrap();
+            if let Some(value) = self.mode.parse(&word) {
+                return Some(value);
+            }
+            //a typo doesn't end the program, the next word is read instead
+            let expected = match self.mode {
+                ValueMode::Hex => "hexadecimal",
+                _ => "decimal",
+            };
+            eprintln!("invalid input \"{word}\", expected a {expected} number, enter it again");
+        }
+    }
+    fn output(&mut self, value: u16) {
+        if self.mode == ValueMode::Ascii {
+            print!("{}", self.mode.format(value));
+            let _ = io::stdout().flush();
+            return;
+        }
+        println!("{}", self.mode.format(value));
+    }
+}
+///input given upfront, output kept, for running programs without a terminal
+#[derive(Debug, Default, derive_new::new)]
+pub struct BufferedIo {
+    pub input: VecDeque<u16>,
+    #[new(default)]
+    pub output: Vec<u16>,
+}
+impl Io for BufferedIo {
+    fn input(&mut self) -> Option<u16> {
+        return self.input.pop_front();
+    }
+    fn output(&mut self, value: u16) {
+        self.output.push(value);
+    }
+}
+
+pub struct Machine {
+    pub memory: Vec<u16>,
+    pub ac: u16,
+    pub pc: u16,
+    pub steps: usize,
+}
+impl Machine {
+    pub fn new(program: &[u16]) -> Self {
+        let mut memory = program.to_vec();
+        memory.resize(MEMORY_SIZE, 0);
+        return Self {
+            memory,
+            ac: 0,
+            pc: 0,
+            steps: 0,
+        };
+    }
+    fn address(value: u16) -> usize {
+        return (value & 0xFFF) as usize;
+    }
+    ///executes instruction at pc, some if the machine stopped
+    pub fn step(&mut self, io: &mut dyn Io) -> Option<StopReason> {
+        let ir = self.memory[Self::address(self.pc)];
+        let x = Self::address(ir);
+        let next = (self.pc + 1) & 0xFFF;
+        let mut pc = next;
+        match ir >> 12 {
+            0x0 => {
+                self.memory[x] = next;
+                pc = (x as u16 + 1) & 0xFFF;
+            }
+            0x1 => self.ac = self.memory[x],
+            0x2 => self.memory[x] = self.ac,
+            0x3 => self.ac = self.ac.wrapping_add(self.memory[x]),
+            0x4 => self.ac = self.ac.wrapping_sub(self.memory[x]),
+            0x5 => match io.input() {
+                Some(value) => self.ac = value,
+                None => return Some(StopReason::InputEnded), //pc stays, so it can be resumed
+            },
+            0x6 => io.output(self.ac),
+            0x7 => {
+                self.steps += 1;
+                return Some(StopReason::Halt);
+            }
+            0x8 => {
+                let value = self.ac as i16;
+                let skip = match (x >> 10) & 0b11 {
+                    0b00 => value < 0,
+                    0b01 => value == 0,
+                    0b10 => value > 0,
+                    _ => false,
+                };
+                if skip {
+                    pc = (pc + 1) & 0xFFF;
+                }
+            }
+            0x9 => pc = x as u16,
+            0xA => self.ac = 0,
+            0xB => {
+                let value = self.memory[Self::address(self.memory[x])];
+                self.ac = self.ac.wrapping_add(value);
+            }
+            0xC => pc = self.memory[x] & 0xFFF,
+            0xD => self.ac = self.memory[Self::address(self.memory[x])],
+            0xE => {
+                let target = Self::address(self.memory[x]);
+                self.memory[target] = self.ac;
+            }
+            _ => {} //0xF is not used by marie
+        }
+        self.pc = pc;
+        self.steps += 1;
+        return None;
+    }
+    ///runs until halt, missing input or max_steps more instructions
+    pub fn run(&mut self, io: &mut dyn Io, max_steps: Option<usize>) -> StopReason {
+        let limit = max_steps.map(|e| self.steps + e);
+        loop {
+            if limit.is_some_and(|e| self.steps >= e) {
+                return StopReason::StepLimit;
+            }
+            if let Some(reason) = self.step(io) {
+                return reason;
+            }
+        }
+    }
+}
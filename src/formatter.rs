@@ -0,0 +1,108 @@
+const INDENT: &str = "    ";
+
+///braces of a line, strings, comments and inline assembly skipped
+#[derive(Default)]
+struct LineScan {
+    opens: usize,
+    closes: usize,
+    leading_closes: usize, //"}" before any other code, they outdent the line itself
+    in_comment: bool,      //line ends inside of "/* */"
+}
+
+fn scan_line(line: &str, in_comment: bool) -> LineScan {
+    let mut scan = LineScan {
+        in_comment,
+        ..Default::default()
+    };
+    let chars: Vec<char> = line.chars().collect();
+    let mut in_string = false;
+    let mut at_statement_start = true; //'%' starts inline assembly only there
+    let mut only_closes = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        i += 1;
+        if scan.in_comment {
+            if c == '*' && next == Some('/') {
+                scan.in_comment = false;
+                i += 1;
+            }
+            continue;
+        }
+        if in_string {
+            match c {
+                '\\' => i += 1,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, next) {
+            ('/', Some('/')) => break,
+            ('/', Some('*')) => {
+                scan.in_comment = true;
+                i += 1;
+            }
+            ('%', _) if at_statement_start => break,
+            ('{', _) => {
+                scan.opens += 1;
+                only_closes = false;
+                at_statement_start = true;
+            }
+            ('}', _) => {
+                scan.closes += 1;
+                if only_closes {
+                    scan.leading_closes += 1;
+                }
+                at_statement_start = true;
+            }
+            (';', _) => at_statement_start = true,
+            (c, _) if c.is_whitespace() => {}
+            ('"', _) => {
+                in_string = true;
+                only_closes = false;
+                at_statement_start = false;
+            }
+            _ => {
+                only_closes = false;
+                at_statement_start = false;
+            }
+        }
+    }
+    return scan;
+}
+
+///indents lines by depth of braces, trims trailing whitespace and leaves at most one blank line in a row,
+///lines inside of "/* */" are kept as they are
+pub fn format(code: &str) -> String {
+    let newline = if code.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut out = String::new();
+    let mut depth: usize = 0;
+    let mut in_comment = false;
+    let mut last_blank = false;
+    for line in code.lines() {
+        let trimmed = line.trim();
+        if in_comment {
+            out.push_str(line.trim_end());
+        } else if trimmed.is_empty() {
+            if last_blank {
+                continue;
+            }
+        } else {
+            let scan = scan_line(trimmed, false);
+            let indent = depth.saturating_sub(scan.leading_closes);
+            out.push_str(&INDENT.repeat(indent));
+            out.push_str(trimmed);
+        }
+        let scan = scan_line(trimmed, in_comment);
+        depth = (depth + scan.opens).saturating_sub(scan.closes);
+        in_comment = scan.in_comment;
+        last_blank = trimmed.is_empty();
+        out.push_str(newline);
+    }
+    if !code.ends_with('\n') && out.ends_with(newline) {
+        out.truncate(out.len() - newline.len());
+    }
+    return out;
+}
@@ -14,9 +14,19 @@ pub enum Symbol {
     Dot,
     Ampersand,
     Minus,
+    Slash,
+    Percent,
+    AngleOpen,
+    AngleClose,
+    Semicolon,
 }
 #[derive(Debug, PartialEq, Clone)]
 pub struct Inline(pub String);
+#[derive(Debug, PartialEq, Clone)]
+pub struct StrLiteral {
+    pub content: String,
+    pub closed: bool, //false when the line ended before the closing quote
+}
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ConditionKind {
@@ -33,6 +43,7 @@ pub enum Token {
     Number(i32),
     Inline(Inline),
     CondKind(ConditionKind),
+    Str(StrLiteral),
 }
 
 #[derive(Debug, PartialEq, Clone, derive_new::new)]
@@ -40,6 +51,10 @@ pub struct TokenLine {
     pub elements: Vec<Token>,
     pub org: String,
     pub line_number: usize,
+    #[new(default)]
+    pub doc: Option<String>, //"///" lines right above
+    #[new(default)]
+    pub columns: Vec<(usize, usize)>, //of every element in the source line, from 0, end exclusive
 }
 
 impl TokenLine {
@@ -87,6 +102,11 @@ fn char_character_as_symbol(ch: char) -> Option<Symbol> {
         '&' => Some(Symbol::Ampersand),
         '-' => Some(Symbol::Minus),
         ':' => Some(Symbol::Colon),
+        '/' => Some(Symbol::Slash),
+        '%' => Some(Symbol::Percent),
+        '<' => Some(Symbol::AngleOpen),
+        '>' => Some(Symbol::AngleClose),
+        ';' => Some(Symbol::Semicolon),
         _ => None,
     };
 }
@@ -100,6 +120,30 @@ fn char_array_starts_with(t: &[char], pattern: &str) -> bool {
     }
     return (0..pattern_ar.len()).all(|i| pattern_ar[i] == t[i]);
 }
+///parses string literal starting at t[0]=='"'
+///supports \n \t \" \\ escapes, unclosed literal takes the rest of the line and is marked as such
+fn tokenize_string(t: &[char]) -> (Token, &[char]) {
+    let mut content = String::new();
+    let mut i = 1;
+    while i < t.len() && t[i] != '"' {
+        if t[i] == '\\' && i + 1 < t.len() {
+            i += 1;
+            content.push(match t[i] {
+                'n' => '\n',
+                't' => '\t',
+                other => other,
+            });
+        } else {
+            content.push(t[i]);
+        }
+        i += 1;
+    }
+    let literal = StrLiteral {
+        content,
+        closed: i < t.len(),
+    };
+    return (Token::Str(literal), &t[(i + 1).min(t.len())..]);
+}
 fn tokenize_next(mut t: &[char]) -> Option<(Token, &[char])> {
     while t.len() > 0 && t[0].is_whitespace() {
         t = &t[1..];
@@ -122,6 +166,10 @@ fn tokenize_next(mut t: &[char]) -> Option<(Token, &[char])> {
         return Some((Token::CondKind(ConditionKind::More), &t[4..]));
     }
 
+    if t[0] == '"' {
+        return Some(tokenize_string(t));
+    }
+
     let simple_token = char_character_as_symbol(t[0]).map(|el| Token::Symbol(el));
     if let Some(val) = simple_token {
         return Some((val, &t[1..]));
@@ -154,6 +202,13 @@ fn tokenize_next(mut t: &[char]) -> Option<(Token, &[char])> {
 }
 
 pub fn tokenize_line(t: &str) -> Vec<Token> {
+    return tokenize_line_with_columns(t)
+        .into_iter()
+        .map(|e| e.0)
+        .collect();
+}
+///tokens with the columns they take in the trimmed line
+fn tokenize_line_with_columns(t: &str) -> Vec<(Token, (usize, usize))> {
     let t = t.trim();
     if t.len() == 0 {
         return Vec::new();
@@ -161,15 +216,28 @@ pub fn tokenize_line(t: &str) -> Vec<Token> {
 
     let chrs: Vec<char> = t.chars().collect();
     if chrs[0] == '%' {
-        return vec![Token::t_inline(t[1..].to_owned())];
+        return vec![(Token::t_inline(t[1..].to_owned()), (0, chrs.len()))];
     }
     let mut ptr: &[char] = chrs.as_slice();
-    let mut tokens: Vec<Token> = Vec::new();
+    let mut tokens = Vec::new();
     loop {
+        let start = chrs.len() - ptr.len() + ptr.iter().take_while(|e| e.is_whitespace()).count();
         let result_maybe = tokenize_next(ptr);
         if let Some(result) = result_maybe {
-            tokens.push(result.0);
+            let starts_statement = matches!(
+                result.0,
+                Symbol(Symbol::Semicolon | Symbol::BraceOpen | Symbol::BraceClose)
+            );
             ptr = result.1;
+            tokens.push((result.0, (start, chrs.len() - ptr.len())));
+            //inline assembly can start any statement, it takes the rest of the line
+            let rest: String = ptr.iter().collect();
+            let rest = rest.trim_start();
+            if starts_statement && rest.starts_with('%') {
+                let start = chrs.len() - rest.chars().count();
+                tokens.push((Token::t_inline(rest[1..].to_owned()), (start, chrs.len())));
+                break;
+            }
         } else {
             break;
         }
@@ -177,23 +245,128 @@ pub fn tokenize_line(t: &str) -> Vec<Token> {
     return tokens;
 }
 
+///replaces /* */ comments with spaces, newlines inside stay so line numbers don't change,
+///strings and inline assembly are left as they are.
+///Also gives line and column of "/*" that is never closed
+fn remove_block_comments(t: &str) -> (String, Option<(usize, usize)>) {
+    let chrs: Vec<char> = t.chars().collect();
+    let mut result = String::with_capacity(t.len());
+    let mut in_block = false;
+    let mut in_string = false;
+    let mut in_line_comment = false;
+    let mut statement_start = true;
+    let mut opened_at = (0, 0);
+    let mut i = 0;
+    while i < chrs.len() {
+        let chr = chrs[i];
+        let next = chrs.get(i + 1).copied();
+        if in_block {
+            if chr == '*' && next == Some('/') {
+                in_block = false;
+                result.push_str("  ");
+                i += 2;
+                continue;
+            }
+            result.push(if chr == '\n' { '\n' } else { ' ' });
+        } else if in_string {
+            if let Some(escaped) = next.filter(|_| chr == '\\') {
+                result.push(chr);
+                result.push(escaped);
+                i += 2;
+                continue;
+            }
+            in_string = chr != '"' && chr != '\n';
+            result.push(chr);
+        } else if in_line_comment {
+            in_line_comment = chr != '\n';
+            result.push(chr);
+        } else {
+            match (chr, next) {
+                ('/', Some('*')) => {
+                    let before = &chrs[..i];
+                    let line = before.iter().filter(|e| **e == '\n').count();
+                    let line_start = before.iter().rposition(|e| *e == '\n').map_or(0, |e| e + 1);
+                    opened_at = (line, i - line_start);
+                    in_block = true;
+                    result.push_str("  ");
+                    i += 2;
+                    continue;
+                }
+                ('/', Some('/')) => in_line_comment = true,
+                ('"', _) => in_string = true,
+                //like the lexer, inline assembly takes the rest of the line
+                ('%', _) if statement_start => in_line_comment = true,
+                _ => {}
+            }
+            statement_start = match chr {
+                ';' | '{' | '}' => true,
+                c if c.is_whitespace() => statement_start,
+                _ => false,
+            };
+            result.push(chr);
+        }
+        if chr == '\n' {
+            statement_start = true;
+        }
+        i += 1;
+    }
+    return (result, in_block.then_some(opened_at));
+}
+///line and column (from 0) of "/*" that is never closed
+pub fn find_unclosed_comment(t: &str) -> Option<(usize, usize)> {
+    return remove_block_comments(t).1;
+}
+
+///";" ends a statement and braces always get their own line,
+///so the parser still sees one statement per TokenLine
+fn split_statements(tokens: Vec<(Token, (usize, usize))>) -> Vec<Vec<(Token, (usize, usize))>> {
+    let mut all = Vec::new();
+    let mut cur = Vec::new();
+    for token in tokens {
+        match token.0 {
+            Symbol(Symbol::Semicolon) => all.push(std::mem::take(&mut cur)),
+            Symbol(Symbol::BraceOpen | Symbol::BraceClose) => {
+                all.push(std::mem::take(&mut cur));
+                all.push(vec![token]);
+            }
+            _ => cur.push(token),
+        }
+    }
+    all.push(cur);
+    return all.into_iter().filter(|e| !e.is_empty()).collect();
+}
+
 pub fn tokenize(t: &str) -> Vec<TokenLine> {
     let mut token_lines: Vec<TokenLine> = Vec::new();
+    let (t, _) = remove_block_comments(t);
     let lines: Vec<_> = t.lines().collect();
+    let mut doc: Option<String> = None;
 
     for i in 0..lines.len() {
         let line = lines[i];
+        let indent = line.chars().take_while(|e| e.is_whitespace()).count();
         let line = line.trim();
         if line == "" {
             continue;
         }
-        let tokens = tokenize_line(line);
-        let token_line = TokenLine::new(tokens, line.to_owned(), i);
-        if token_line.elements.len() == 0 {
+        if let Some(doc_line) = line.strip_prefix("///") {
+            let doc = doc.get_or_insert_with(String::new);
+            if !doc.is_empty() {
+                doc.push('\n');
+            }
+            doc.push_str(doc_line.trim());
             continue;
         }
-
-        token_lines.push(token_line);
+        for tokens in split_statements(tokenize_line_with_columns(line)) {
+            let (tokens, columns): (Vec<_>, Vec<_>) = tokens.into_iter().unzip();
+            let mut token_line = TokenLine::new(tokens, line.to_owned(), i);
+            token_line.doc = doc.take();
+            token_line.columns = columns
+                .into_iter()
+                .map(|e| (e.0 + indent, e.1 + indent))
+                .collect();
+            token_lines.push(token_line);
+        }
     }
     return token_lines;
 }
@@ -0,0 +1,194 @@
+//!crystal-marie compiler as a library, `compile_source` runs the whole pipeline,
+//!assembler and emulator run its output
+
+use compiler::CodeOrigin;
+use lexer::TokenLine;
+use lib_handler::{LineOrigin, MergedCode};
+use parser::ProgramTree;
+use parser::VariableId;
+use std::collections::HashMap;
+
+pub mod assembler;
+mod compiler;
+mod diagnostics;
+pub mod emulator;
+mod lexer;
+mod lib_handler;
+mod parser;
+mod string_builder;
+mod token_expect;
+mod type_checker;
+mod utility;
+mod warnings;
+
+//used by the crystal-marie binary, not part of the library api
+#[doc(hidden)]
+pub mod debugger;
+#[doc(hidden)]
+pub mod dump;
+#[doc(hidden)]
+pub mod formatter;
+#[doc(hidden)]
+pub mod listing;
+#[doc(hidden)]
+pub mod source_map;
+#[doc(hidden)]
+pub mod test_runner;
+
+pub use diagnostics::{Diagnostic, Severity};
+
+///file given to the compiler, the first one is the main file, others are libs in order
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Source {
+    pub name: String,
+    pub code: String,
+}
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub warnings_as_errors: bool, //warnings fail the compilation
+    pub source_comments: bool,    //"/ file:line source" above code of every statement
+}
+///what diagnostics and dumps point into
+#[derive(Debug, Default)]
+pub struct SourceFiles {
+    pub(crate) code: MergedCode,
+    pub(crate) files: Vec<String>, //indexed by LineOrigin::file
+    pub(crate) tokens: Vec<TokenLine>,
+}
+impl SourceFiles {
+    ///name of the file the line of merged code comes from and the line in it, from 0
+    pub fn get_origin(&self, line: usize) -> (&str, usize) {
+        let origin = self.code.get_origin(line);
+        let file = self.files.get(origin.file).map_or("", |e| e.as_str());
+        return (file, origin.line);
+    }
+    ///text of the line of merged code
+    pub fn get_line(&self, line: usize) -> Option<&str> {
+        return self.code.get_line(line);
+    }
+    ///tokens of the line of merged code, as debug text
+    pub fn get_tokens_text(&self, line: usize) -> String {
+        let tokens = self.tokens.iter().find(|e| e.line_number == line);
+        return format!("{:?}", tokens.map_or(&Vec::new(), |e| &e.elements));
+    }
+}
+#[derive(Debug)]
+pub struct Output {
+    pub code: String, //marie assembly
+    pub(crate) origins: Vec<CodeOrigin>,
+    pub(crate) address_map: HashMap<VariableId, usize>,
+    pub(crate) tree: ProgramTree,
+    pub warnings: Vec<Diagnostic>,
+    pub sources: SourceFiles,
+}
+///errors of a failed compilation, sorted by line
+#[derive(Debug)]
+pub struct Diagnostics {
+    pub list: Vec<Diagnostic>,
+    pub sources: SourceFiles,
+}
+
+///merges libs into the main file (the first one) and splits it into tokens,
+///block comment that isn't closed in its own file is an error
+pub(crate) fn tokenize_source(sources: &[Source]) -> Result<SourceFiles, Diagnostics> {
+    let main = sources.first().map_or("", |e| e.code.as_str());
+    let libs: Vec<&str> = sources.iter().skip(1).map(|e| e.code.as_str()).collect();
+    let code = lib_handler::merge(main, &libs);
+    let tokens = lexer::tokenize(&code.code);
+    let files = SourceFiles {
+        files: sources.iter().map(|e| e.name.clone()).collect(),
+        code,
+        tokens,
+    };
+    let list: Vec<Diagnostic> = sources
+        .iter()
+        .enumerate()
+        .filter_map(|(file, source)| {
+            let (line, column) = lexer::find_unclosed_comment(&source.code)?;
+            let mut diagnostic = Diagnostic::new(
+                diagnostics::Severity::Error,
+                diagnostics::UNCLOSED_COMMENT,
+                files.code.find_line(LineOrigin { file, line }).unwrap_or(0),
+                "Block comment was not closed with */".to_owned(),
+            );
+            diagnostic.columns = Some((column, column + 2));
+            return Some(diagnostic);
+        })
+        .collect();
+    if !list.is_empty() {
+        return Err(Diagnostics {
+            list,
+            sources: files,
+        });
+    }
+    return Ok(files);
+}
+///only syntax and names are checked, not types
+pub(crate) fn parse_source(files: &SourceFiles) -> Result<ProgramTree, Vec<Diagnostic>> {
+    return parser::parse(files.tokens.as_slice()).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|e| Diagnostic::from_error(e, diagnostics::PARSE_ERROR))
+            .collect()
+    });
+}
+
+///merges libs into the main file, parses, checks and compiles it
+pub fn compile_source(sources: &[Source], options: &Options) -> Result<Output, Diagnostics> {
+    if sources.is_empty() {
+        return Err(Diagnostics {
+            list: vec![Diagnostic::new(
+                diagnostics::Severity::Error,
+                diagnostics::PARSE_ERROR,
+                0,
+                "no source given".to_owned(),
+            )],
+            sources: SourceFiles::default(),
+        });
+    }
+    let map = tokenize_source(sources)?;
+
+    let maybe_parsed = parse_source(&map).and_then(|tree| {
+        type_checker::check(&tree, &map.tokens)
+            .map(|_| tree)
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|e| Diagnostic::from_error(e, diagnostics::TYPE_MISMATCH))
+                    .collect()
+            })
+    });
+    let tree = match maybe_parsed {
+        Ok(tree) => tree,
+        Err(list) => return Err(Diagnostics { list, sources: map }),
+    };
+
+    //warnings of libs are not interesting to whoever uses them
+    let warnings: Vec<_> = warnings::check(&tree)
+        .into_iter()
+        .filter(|e| map.code.get_origin(e.line).file == 0)
+        .filter(|e| !warnings::is_suppressed(map.code.get_line(e.line).unwrap_or("")))
+        .map(|e| Diagnostic::from_warning(e, options.warnings_as_errors))
+        .collect();
+    if options.warnings_as_errors && !warnings.is_empty() {
+        return Err(Diagnostics {
+            list: warnings,
+            sources: map,
+        });
+    }
+    let compiled = compiler::compile_with_origins(tree.clone());
+    let code = match options.source_comments {
+        true => {
+            listing::add_source_comments(&compiled.code, &compiled.origins, &map.code, &map.files)
+        }
+        false => compiled.code,
+    };
+    return Ok(Output {
+        code,
+        origins: compiled.origins,
+        address_map: compiled.address_map,
+        tree,
+        warnings,
+        sources: map,
+    });
+}
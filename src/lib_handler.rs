@@ -1,21 +1,103 @@
-pub fn merge(code: &str, lib_codes: &Vec<&str>) -> String {
-    let all_lib: String = lib_codes
+///where a line of merged code comes from, file 0 is the main file, libs follow in given order
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LineOrigin {
+    pub file: usize,
+    pub line: usize, // starts from 0
+}
+///merged code with origin of each of its lines
+#[derive(Debug, Default)]
+pub struct MergedCode {
+    pub code: String,
+    pub origins: Vec<LineOrigin>,
+}
+impl MergedCode {
+    pub fn get_origin(&self, line: usize) -> LineOrigin {
+        return self.origins.get(line).copied().unwrap_or_default();
+    }
+    pub fn get_line(&self, line: usize) -> Option<&str> {
+        return self.code.lines().nth(line);
+    }
+    ///line of merged code that starts with the given line of a file
+    pub fn find_line(&self, origin: LineOrigin) -> Option<usize> {
+        return self.origins.iter().position(|e| *e == origin);
+    }
+}
+
+///text with origin of every byte, so merging can move it around
+#[derive(Default)]
+struct TracedText {
+    text: String,
+    origins: Vec<LineOrigin>,
+}
+impl TracedText {
+    fn new(text: &str, file: usize) -> Self {
+        let mut origins = Vec::with_capacity(text.len());
+        let mut line = 0;
+        for byte in text.bytes() {
+            origins.push(LineOrigin { file, line });
+            if byte == b'\n' {
+                line += 1;
+            }
+        }
+        return Self {
+            text: text.to_owned(),
+            origins,
+        };
+    }
+    ///replaces bytes at from..to with other
+    fn splice(mut self, from: usize, to: usize, other: TracedText) -> Self {
+        self.text.replace_range(from..to, &other.text);
+        self.origins.splice(from..to, other.origins);
+        return self;
+    }
+    ///lines take origin of their first non whitespace char
+    fn into_merged(self) -> MergedCode {
+        let mut origins = Vec::new();
+        let mut offset = 0;
+        for line in self.text.split_inclusive('\n') {
+            let first = line.find(|c: char| !c.is_whitespace()).unwrap_or(0);
+            origins.push(self.origins[offset + first]);
+            offset += line.len();
+        }
+        return MergedCode {
+            code: self.text,
+            origins,
+        };
+    }
+}
+
+pub fn merge(code: &str, lib_codes: &Vec<&str>) -> MergedCode {
+    let all_lib = lib_codes
         .iter()
+        .enumerate()
         .rev()
-        .fold(String::new(), |acc, lib| merge_single(&acc, lib));
+        .fold(TracedText::default(), |acc, (i, lib)| {
+            merge_single(acc, TracedText::new(lib, i + 1))
+        });
 
-    let r = merge_single(code, &all_lib);
-    return r;
+    let r = merge_single(TracedText::new(code, 0), all_lib);
+    return r.into_merged();
 }
 
-pub fn merge_single(code: &str, lib_code: &str) -> String {
-    if code.is_empty() {
-        return lib_code.to_owned();
+fn merge_single(code: TracedText, lib_code: TracedText) -> TracedText {
+    if code.text.is_empty() {
+        return lib_code;
+    }
+    if lib_code.text.is_empty() {
+        return code;
     }
-    if lib_code.is_empty() {
-        return code.to_owned();
+    let mut offset = 0;
+    for line in code.text.split_inclusive('\n') {
+        if is_globals_end(line) {
+            let star = offset + line.find('*').unwrap();
+            return code.splice(star, star + 1, lib_code);
+        }
+        offset += line.len();
     }
-    let pre_code: String = code.chars().into_iter().take_while(|e| *e != '*').collect();
-    let after_code = &code[pre_code.len() + 1..];
-    return format!("{pre_code}{lib_code}{after_code}");
+    let end = code.text.len();
+    return code.splice(end, end, lib_code);
+}
+//globals end with a line that is only "*", comments before it can contain '*' too
+fn is_globals_end(line: &str) -> bool {
+    return line.split("//").next().unwrap().trim() == "*";
 }
@@ -0,0 +1,71 @@
+use crate::{
+    assembler::Assembled,
+    compiler::{find_origin, CodeOrigin},
+    lib_handler::MergedCode,
+    Output,
+};
+
+fn location_text(origin: &CodeOrigin, code: &MergedCode, files: &[String]) -> String {
+    let line = code.get_origin(origin.line);
+    let file = files.get(line.file).map_or("", |e| e.as_str());
+    return format!("{}:{}", file, line.line + 1);
+}
+///code of the source line, without comment
+fn source_text(origin: &CodeOrigin, code: &MergedCode) -> String {
+    let line = code.get_line(origin.line).unwrap_or("");
+    return line.split("//").next().unwrap().trim().to_owned();
+}
+//lines of marie code that are only a comment don't take a word
+fn is_word(line: &str) -> bool {
+    return !line.split('/').next().unwrap().trim().is_empty();
+}
+
+///address, machine code and marie code of every word with the source line it comes from
+pub fn listing(assembled: &Assembled, output: &Output) -> String {
+    let lines: Vec<&str> = output.code.lines().collect();
+    let sources = &output.sources;
+    let mut out = String::new();
+    for (address, word) in assembled.memory.iter().enumerate() {
+        let text = lines[assembled.lines[address]]
+            .split('/')
+            .next()
+            .unwrap()
+            .trim();
+        let location = find_origin(&output.origins, address).map_or(String::new(), |e| {
+            location_text(e, &sources.code, &sources.files)
+        });
+        out.push_str(format!("{address:03X}  {word:04X}  {text:<40}{location}").trim_end());
+        out.push('\n');
+    }
+    return out.trim_end().to_owned();
+}
+
+///puts "/ file:line source" above code of every statement
+pub(crate) fn add_source_comments(
+    asm: &str,
+    origins: &[CodeOrigin],
+    code: &MergedCode,
+    files: &[String],
+) -> String {
+    let mut out = Vec::new();
+    let mut address = 0;
+    let mut last: Option<&CodeOrigin> = None;
+    for line in asm.lines() {
+        if !is_word(line) {
+            out.push(line.to_owned());
+            continue;
+        }
+        let origin = find_origin(origins, address);
+        if let Some(origin) = origin.filter(|e| Some(*e) != last) {
+            out.push(format!(
+                "/ {} {}",
+                location_text(origin, code, files),
+                source_text(origin, code)
+            ));
+        }
+        last = origin;
+        out.push(line.to_owned());
+        address += 1;
+    }
+    return out.join("\n");
+}
@@ -1,17 +1,245 @@
-use std::{env, fs, process::exit};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process::exit,
+};
 
-use config::OutputMethod;
+use config::{Command, Config, DiagnosticsFormat, EmitKind, OutputMethod};
+use crystal_marie::{
+    assembler::{self, Assembled},
+    debugger::Debugger,
+    dump,
+    emulator::{Machine, StdIo, StopReason},
+    formatter, listing, source_map, test_runner, Diagnostic, Output, Severity, Source, SourceFiles,
+};
 
 mod argument_parser;
-mod compiler;
 mod config;
-mod lexer;
-mod lib_handler;
-mod parser;
-mod string_builder;
-mod token_expect;
-mod utility;
-fn print_help() {}
+
+const HELP: &str = "\
+usage: crystal-marie [command] file.crmarie [options]
+
+commands:
+  build    compile to marie assembly (default)
+  run      compile and run on the built-in emulator, input is read from stdin
+  check    report errors and warnings without writing anything
+  fmt      format the file to stdout, -i rewrites it
+  test     run tests found under the given path (current directory by default), see readme
+  debug    run on the built-in emulator with a source level debugger, type help for its commands
+
+options:
+  -l, --lib <files>          include lib files, can be repeated, order matters
+  -o, --output <file>        output file name (a.marie for marie code, stdout otherwise)
+  -s, --stdout               write output to stdout
+  -i, --write                fmt rewrites the input file
+      --emit <kind>          what to output: tokens, ast, ir, asm or bin
+      --listing              output every word with its address and source line
+      --source-comments      put source lines as comments into marie code
+      --source-map [file]    write json source map (output file name + .map by default)
+  -W error                   treat warnings as errors
+  -e, --max-errors <count>   print at most count errors
+  -d, --diagnostics <kind>   text or json
+      --io-mode <mode>       input and output of run: dec, hex or ascii
+      --max-steps <count>    stop run after count instructions
+  -h, --help                 print this help
+  -V, --version              print version";
+
+fn print_help() {
+    println!("{HELP}");
+}
+fn write_output(output: &OutputMethod, text: &str) {
+    match output {
+        OutputMethod::File(file_name) => {
+            let write_res = fs::write(file_name, text);
+            if let Err(err) = write_res {
+                eprintln!("err while writing to file: {}", err);
+                exit(1);
+            }
+        }
+        OutputMethod::Stdout => println!("{}", text),
+    }
+}
+fn format_file(arguments: &Config, input_file_name: &str, code: &str) {
+    let formatted = formatter::format(code);
+    if arguments.write {
+        write_output(&OutputMethod::File(input_file_name.to_owned()), &formatted);
+        return;
+    }
+    match &arguments.output {
+        None | Some(OutputMethod::Stdout) => print!("{}", formatted),
+        Some(output) => write_output(output, &formatted),
+    }
+}
+fn assemble(code: &str) -> Assembled {
+    let assembled = assembler::assemble(code);
+    if let Err(err) = assembled {
+        eprintln!(
+            "err while assembling line {}: {}",
+            err.line + 1,
+            err.content
+        );
+        exit(1);
+    }
+    return assembled.unwrap();
+}
+fn run_program(arguments: &Config, code: &str) {
+    let mut machine = Machine::new(&assemble(code).memory);
+    let mut io = StdIo::new(arguments.value_mode);
+    match machine.run(&mut io, arguments.max_steps) {
+        StopReason::Halt => {}
+        StopReason::StepLimit => {
+            eprintln!("stopped after {} steps", machine.steps);
+            exit(1);
+        }
+        StopReason::InputEnded => {
+            eprintln!("program asked for input, but stdin ended");
+            exit(1);
+        }
+    }
+}
+fn read_libs(arguments: &Config) -> Vec<Source> {
+    let lib_code: Result<Vec<_>, _> = arguments
+        .lib
+        .iter()
+        .map(|e| fs::read_to_string(e.trim()).map(|code| Source::new(e.trim().to_owned(), code)))
+        .collect();
+    if let Err(err) = lib_code {
+        eprintln!("lib couldn't be loaded:{} ", err);
+        exit(1);
+    }
+    return lib_code.unwrap();
+}
+///runs every test file under the input path (current directory by default)
+fn test_programs(arguments: &Config) {
+    let path = arguments.input_file_name.as_deref().unwrap_or(".").trim();
+    let libs = read_libs(arguments);
+    let max_steps = arguments
+        .max_steps
+        .unwrap_or(test_runner::DEFAULT_MAX_STEPS);
+    let files = test_runner::discover(Path::new(path), arguments.value_mode);
+    if let Err(err) = files {
+        eprintln!("err while looking for tests: {}", err);
+        exit(1);
+    }
+    let files = files.unwrap();
+    if files.is_empty() {
+        eprintln!("no tests found in {path}");
+        exit(1);
+    }
+    let mut failed = 0;
+    for file in &files {
+        let name = file.path.display();
+        match test_runner::run_file(file, &libs, arguments.value_mode, max_steps) {
+            Ok(problems) if problems.is_empty() => {
+                println!("ok    {name} ({} cases)", file.cases.len())
+            }
+            Ok(problems) => {
+                failed += 1;
+                println!("FAIL  {name}");
+                for problem in problems {
+                    println!("  {problem}");
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                println!("FAIL  {name}\n  {err}");
+            }
+        }
+    }
+    println!("\n{} passed, {failed} failed", files.len() - failed);
+    if failed > 0 {
+        exit(1);
+    }
+}
+///reads debugger commands from stdin until quit, program input comes from stdin too
+fn debug_program(arguments: &Config, compiled: &Output) {
+    let assembled = assemble(&compiled.code);
+    let mut debugger = Debugger::new(compiled, &assembled.memory, arguments.max_steps);
+    let mut io = StdIo::new(arguments.value_mode);
+    io.prompt = true;
+    println!("{}", debugger.execute("where", &mut io).unwrap());
+    loop {
+        print!("(debug) ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let command = line.trim();
+        if command == "quit" || command == "q" {
+            break;
+        }
+        match debugger.execute(command, &mut io) {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => println!("{text}"),
+            Err(err) => eprintln!("err: {err}"),
+        }
+    }
+}
+///prints message with the line it points to
+fn print_lined(prefix: &str, diagnostic: &Diagnostic, sources: &SourceFiles) {
+    eprintln!(
+        "{}{}\n\nat line:{}\n\"{}\"\n\nTokens at current line:\n\"{}\"",
+        prefix,
+        diagnostic.message,
+        sources.get_origin(diagnostic.line).1 + 1,
+        sources
+            .get_line(diagnostic.line)
+            .unwrap_or("Error while trying to get line giving error"),
+        sources.get_tokens_text(diagnostic.line)
+    );
+}
+fn print_diagnostics(
+    diagnostics: &[Diagnostic],
+    format: &DiagnosticsFormat,
+    sources: &SourceFiles,
+) {
+    for diagnostic in diagnostics {
+        if *format == DiagnosticsFormat::Json {
+            eprintln!("{}", diagnostic.to_json(sources));
+            continue;
+        }
+        let prefix = match diagnostic.severity {
+            Severity::Error => "",
+            Severity::Warning => "warning: ",
+        };
+        print_lined(prefix, diagnostic, sources);
+        eprintln!();
+    }
+}
+///errors of a failed compilation, at most as many as --max-errors says
+fn print_errors(arguments: &Config, errors: &[Diagnostic], sources: &SourceFiles) {
+    let count = errors.len();
+    let max = arguments.max_errors.unwrap_or(count);
+    print_diagnostics(&errors[..max.min(count)], &arguments.diagnostics, sources);
+    if count > max && arguments.diagnostics == DiagnosticsFormat::Text {
+        eprintln!("{} more errors not shown", count - max);
+    }
+}
+///marie code goes to a.marie, dumps to stdout unless -o or -s says otherwise
+fn get_output(arguments: &Config) -> OutputMethod {
+    return match (&arguments.output, arguments.emit) {
+        (Some(output), _) => output.clone(),
+        (None, EmitKind::Asm) => OutputMethod::File("a.marie".to_owned()),
+        (None, _) => OutputMethod::Stdout,
+    };
+}
+///tokens and ast are written before type checking and code generation,
+///so they can be looked at when those fail
+fn emit_early_stage(arguments: &Config, sources: &[Source]) {
+    let text = match arguments.emit {
+        EmitKind::Tokens => dump::dump_tokens(sources),
+        _ => dump::dump_ast(sources),
+    };
+    match text {
+        Ok(text) => write_output(&get_output(arguments), &text),
+        Err(errors) => {
+            print_errors(arguments, &errors.list, &errors.sources);
+            exit(1)
+        }
+    }
+}
 fn main() {
     let envs: Vec<String> = env::args().collect();
     let arguments = config::parse_arguments(&envs[1..]);
@@ -25,12 +253,20 @@ fn main() {
         print_help();
         exit(0);
     }
+    if arguments.version_mode {
+        println!("crystal-marie {}", env!("CARGO_PKG_VERSION"));
+        exit(0);
+    }
 
+    if arguments.command == Command::Test {
+        test_programs(&arguments);
+        return;
+    }
     if arguments.input_file_name.is_none() {
         eprintln!("err: input file name not specified");
         exit(1);
     }
-    let input_file_name = arguments.input_file_name.unwrap();
+    let input_file_name = arguments.input_file_name.clone().unwrap();
     let code = fs::read_to_string(input_file_name.trim());
 
     if let Err(err) = code {
@@ -39,69 +275,77 @@ fn main() {
     }
 
     //push the lib at the beginning
-    let mut main_code = code.unwrap();
-
-    let mut line_dif = 1; // its one cause its hacky way to avoid it getting in the way if not used
+    let main_code = code.unwrap();
+    if arguments.command == Command::Fmt {
+        format_file(&arguments, input_file_name.trim(), &main_code);
+        return;
+    }
 
-    if !arguments.lib.is_empty() {
-        let lib_code: Result<Vec<_>, _> = arguments
-            .lib
-            .iter()
-            .map(|e| fs::read_to_string(e.trim()))
+    let sources: Vec<Source> =
+        std::iter::once(Source::new(input_file_name.trim().to_owned(), main_code))
+            .chain(read_libs(&arguments))
             .collect();
-        if let Err(err) = lib_code {
-            eprintln!("lib couldn't be loaded:{} ", err);
-            exit(1);
-        }
-        let lib_code = lib_code.unwrap();
+    let options = crystal_marie::Options {
+        warnings_as_errors: arguments.warnings_as_errors,
+        source_comments: arguments.source_comments,
+    };
 
-        line_dif = lib_code
-            .iter()
-            .map(|e| e.lines().count() - 1)
-            .sum::<usize>()
-            + 2;
-
-        main_code = lib_handler::merge(
-            &main_code,
-            &lib_code.iter().map(|e| e.as_str()).collect::<Vec<&str>>(),
-        );
+    let early_stage = matches!(arguments.emit, EmitKind::Tokens | EmitKind::Ast);
+    if early_stage && arguments.command == Command::Build {
+        emit_early_stage(&arguments, &sources);
+        //source map still needs the whole compilation
+        if arguments.source_map.is_none() {
+            return;
+        }
     }
 
-    let tokens = lexer::tokenize(&main_code);
-
-    let maybe_parsed = parser::parse(tokens.as_slice());
-
-    if let Err(err) = maybe_parsed {
-        eprintln!(
-            "{}\n\nat line:{}\n\"{}\"\n\nTokens at current line:\n\"{:?}\"",
-            err.content,
-            err.line as i32 + 1 - (line_dif - 1) as i32,
-            //           line_to_file.iter().find(|e| )
-            main_code
-                .lines()
-                .nth(err.line)
-                .unwrap_or("Error while trying to get line giving error"), // it can go negative in some cases (kinda bug)
-            tokens
-                .iter()
-                .filter(|e| e.line_number == err.line)
-                .nth(0)
-                .unwrap()
-                .elements
-        );
+    let compiled = crystal_marie::compile_source(&sources, &options);
+    if let Err(errors) = compiled {
+        print_errors(&arguments, &errors.list, &errors.sources);
         exit(1)
     }
-    let reprs = maybe_parsed.unwrap();
-    let code = compiler::compile(reprs);
+    let compiled = compiled.unwrap();
+    print_diagnostics(
+        &compiled.warnings,
+        &arguments.diagnostics,
+        &compiled.sources,
+    );
+    if arguments.command == Command::Check {
+        return;
+    }
+    let code = &compiled.code;
 
-    match arguments.output {
-        OutputMethod::File(file_name) => {
-            let write_res = fs::write(file_name, code);
-            if let Err(err) = write_res {
-                eprintln!("err while writing to file: {}", err);
-                exit(1);
-            }
+    if arguments.command == Command::Run {
+        run_program(&arguments, code);
+        return;
+    }
+    if arguments.command == Command::Debug {
+        debug_program(&arguments, &compiled);
+        return;
+    }
+    let text = match arguments.emit {
+        EmitKind::Tokens | EmitKind::Ast => None, //already written
+        EmitKind::Ir | EmitKind::Bin | EmitKind::Listing => {
+            let assembled = assemble(code);
+            Some(match arguments.emit {
+                EmitKind::Ir => dump::dump_ir(&assembled, code),
+                EmitKind::Bin => dump::dump_bin(&assembled),
+                _ => listing::listing(&assembled, &compiled),
+            })
         }
-        OutputMethod::Stdout => println!("{}", code),
+        EmitKind::Asm => Some(code.clone()),
+    };
+    let output = get_output(&arguments);
+    if let Some(text) = text {
+        write_output(&output, &text);
+    }
+
+    if let Some(file) = &arguments.source_map {
+        let file = file.clone().unwrap_or_else(|| match output {
+            OutputMethod::File(name) => format!("{name}.map"),
+            OutputMethod::Stdout => "a.marie.map".to_owned(),
+        });
+        write_output(&OutputMethod::File(file), &source_map::to_json(&compiled));
     }
 }
 
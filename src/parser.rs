@@ -1,11 +1,12 @@
+use crate::diagnostics;
+use crate::lexer::tokenize_line;
 use crate::lexer::ConditionKind;
 use crate::lexer::Inline;
+use crate::lexer::StrLiteral;
 use crate::lexer::Symbol;
 use crate::lexer::Symbol::*;
 use crate::lexer::Token;
 use crate::lexer::TokenLine;
-use crate::token_expect::match_exact_ok;
-use crate::utility::GeneralExt;
 use crate::utility::PackedResultExt;
 use bitflags::bitflags;
 use std::collections::HashSet;
@@ -14,7 +15,6 @@ use crate::token_expect::match_exact;
 use crate::token_expect::match_exact_cond;
 use crate::token_expect::IndexReq;
 use crate::token_expect::TokenReq;
-use crate::utility;
 use crate::utility::push_slice;
 use crate::utility::LinedError;
 use crate::utility::SingleStep;
@@ -24,11 +24,16 @@ use crate::utility::StepH;
 use crate::utility::SuccessStep;
 
 pub type ParserError = LinedError<String>;
-pub type ParserResult<T> = Result<T, LinedError<String>>;
 
 #[derive(Debug, PartialEq, Clone, Copy, Default, Hash, Eq)]
 pub struct FunctionId(usize);
 
+///raw indexes Function::flags of fnc
+#[derive(derive_new::new, Debug, PartialEq, Clone, Copy, Hash, Eq, Default)]
+pub struct FlagId {
+    pub raw: usize,
+    pub fnc: FunctionId,
+}
 #[derive(derive_new::new, Debug, PartialEq, Clone, Copy, Hash, Eq, Default)]
 pub struct VariableId {
     pub raw: usize,
@@ -40,10 +45,31 @@ pub enum VariableType {
     Global,
     Local(FunctionId),
 }
+#[derive(Debug, PartialEq, Clone, Copy, Default, Hash, Eq)]
+pub struct StructId(usize);
+
+///what variable holds, plain word unless annotated
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DataType {
+    #[default]
+    Word, //untyped, compatible with everything
+    Int,
+    Ptr,
+    Struct(StructId), //takes as many words as the struct has fields
+    StructPtr(StructId),
+}
 #[derive(Debug, PartialEq, derive_new::new)]
 pub struct VariableDeclaration {
     pub name: String,
     pub default_value: i32,
+    #[new(default)]
+    pub data_type: DataType,
+    #[new(default)]
+    pub doc: Option<String>,
+    #[new(default)]
+    pub is_mut: bool, //"mut" argument, can be assigned to
+    #[new(default)]
+    pub line: usize,
 }
 #[derive(Debug, PartialEq, Clone, derive_new::new)]
 pub struct Variable {
@@ -51,9 +77,33 @@ pub struct Variable {
     pub default_value: i32,
     pub id: VariableId, //not perfect that it has it. can be in wrong state (same for functions)
     pub read_only: bool,
+    #[new(default)]
+    pub data_type: DataType,
+    #[new(default)]
+    pub doc: Option<String>, //from "///" above the declaration
+    #[new(default)]
+    pub line: usize, //line of the declaration
+}
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Struct {
+    pub id: StructId,
+    pub name: String,
+    pub fields: Vec<String>, //offset of the field is its index
+}
+///"const NAME = value", substituted as a literal wherever it's used
+#[derive(Debug, Clone, derive_new::new)]
+pub struct NamedConstant {
+    pub name: String,
+    pub value: i32,
 }
 
-pub type Block = Vec<Statement>;
+///statement with the source line it starts on
+#[derive(Debug, Clone, derive_new::new)]
+pub struct LineStatement {
+    pub statement: Statement,
+    pub line: usize,
+}
+pub type Block = Vec<LineStatement>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ArgumentCallArg {
@@ -61,13 +111,37 @@ pub enum ArgumentCallArg {
     Reference(VariableId),
     Deref(VariableId),
     GetAddress(VariableId),
-    Flag(String, FunctionId), //TODO usage of string here is bad, could be solved with additional passthrough for flags
+    Flag(FlagId),
+    Str(String), //evaluates to the address of zero terminated string
+    Operation(Box<Operation>),
+    Field(VariableId, usize),    //p.x
+    PtrField(VariableId, usize), //p->x
+}
+///something that can be assigned to
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Place {
+    Variable(VariableId),
+    Field(VariableId, usize),
+    PtrField(VariableId, usize),
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OperationKind {
+    Mul,
+    Div,
+    Mod,
+}
+///a OP b, value ends up in acc
+#[derive(Debug, PartialEq, Clone, derive_new::new)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub a: ArgumentCallArg,
+    pub b: ArgumentCallArg,
 }
 #[derive(Debug, PartialEq, derive_new::new, Clone)]
 pub struct FunctionCall {
     pub fnc_id: FunctionId,
     pub arguments: Vec<ArgumentCallArg>,
-    pub assignment: Option<VariableId>,
+    pub assignment: Option<Place>,
     pub from: FunctionId,
 }
 #[derive(Debug, Clone)]
@@ -79,9 +153,17 @@ pub struct If {
     pub cond: ConditionKind,
 }
 
+///cases hold (value, block), default is taken when no case matches
+#[derive(Debug, Clone, derive_new::new)]
+pub struct Switch {
+    pub value: ArgumentCallArg,
+    pub cases: Vec<(i32, Block)>,
+    pub default: Option<Block>,
+}
+
 #[derive(Debug, Clone, derive_new::new)]
 pub struct Flag {
-    pub label: String,
+    pub id: FlagId,
 }
 #[derive(Debug, Clone, derive_new::new)]
 pub struct Ret {
@@ -91,25 +173,41 @@ pub struct Ret {
 
 #[derive(Debug, Clone, derive_new::new)]
 pub struct Assignment {
-    pub left: VariableId,
+    pub left: Place,
     pub right: ArgumentCallArg,
 }
 
+///piece of inline assembly, {x} and {@flag} are resolved by the parser
+#[derive(Debug, Clone)]
+pub enum InlinePart {
+    Text(String),
+    Variable(VariableId),
+    Field(VariableId, usize),
+    Constant(i32),
+    Flag(FlagId),
+}
+
 #[derive(Debug, Clone)]
 pub enum Statement {
-    Inline(String),
+    Inline(Vec<InlinePart>),
     If(If),
     FunctionCall(FunctionCall),
     Flag(Flag),
+    Goto(FlagId),
+    Switch(Switch),
     Ret(Ret),
     Assignment(Assignment),
 }
 #[derive(Debug, derive_new::new)]
 pub struct FunctionDeclaration {
     pub name: String,
-    pub arguments: Vec<String>,
+    pub arguments: Vec<VariableDeclaration>,
     pub locals: Vec<VariableDeclaration>,
     pub is_stack: bool,
+    #[new(default)]
+    pub doc: Option<String>,
+    #[new(default)]
+    pub line: usize,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -120,37 +218,106 @@ pub struct Function {
     pub locals: Vec<Variable>,
     pub args: usize,
     pub is_stack: bool, //use bitflags instead if more bools appear
+    pub flags: Vec<String>,
+    pub doc: Option<String>, //from "///" above the declaration
+    pub line: usize,
 }
 bitflags! {
-    #[derive(Debug,Default)]
+    #[derive(Debug,Default,Clone)]
     pub struct FeatureFlags: u32{
         const StackFunctions= 1<<0;
+        const Multiplication= 1<<1;
+        const Division= 1<<2;
+        const StructPointers= 1<<3;
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct ProgramTree {
     pub functions: Vec<Function>, //fnc[0]== main
     pub globals: Vec<Variable>,   //globals[1]== ret, globals[0]==temp
     pub constants_used: HashSet<i32>,
+    pub strings_used: Vec<String>, //vec so the order in output is stable
+    pub addresses_used: HashSet<VariableId>,
+    pub structs: Vec<Struct>,
+    pub named_constants: Vec<NamedConstant>,
     pub features: FeatureFlags,
 }
 
+///what statements of a function need from the compiler, added to the tree once it's parsed
+#[derive(Default)]
+struct FunctionUses {
+    constants: HashSet<i32>,
+    strings: Vec<String>,
+    features: FeatureFlags,
+    operation_temps: usize,
+    addresses: HashSet<VariableId>,
+}
 #[derive(derive_new::new)]
 struct BuildingContext<'a> {
     cur_function: &'a Function,
     cur_tree: &'a ProgramTree,    //unfinished tree
     upper: Option<&'a Statement>, //kinda removed
-    constants: HashSet<i32>,
+    uses: FunctionUses,
+    cur_line: TokenLine, //line of the statement being parsed
+    #[new(default)]
+    flags: Vec<String>, //names of cur_function flags, index is FlagId::raw
+    #[new(default)]
+    flag_marks: Vec<FlagMark>, //checked once the function is parsed
+    #[new(default)]
+    errors: Vec<ParserError>, //statements that failed, parsing goes on after them
+}
+///definition or use of a flag
+#[derive(derive_new::new)]
+struct FlagMark {
+    raw: usize,
+    line: TokenLine,
+    is_definition: bool,
 }
 
 fn is_main(f: &Function) -> bool {
     return f.name == "main";
 }
 
+///error before it's known which line it's on, ParserError::upgrade puts it on one
+#[derive(Debug, Clone, PartialEq, derive_new::new)]
+pub struct ParseIssue {
+    pub code: &'static str,
+    pub subject: Option<Token>, //token the error is about, diagnostics point at it
+    pub message: String,
+}
+impl From<String> for ParseIssue {
+    fn from(message: String) -> Self {
+        return Self::new(diagnostics::PARSE_ERROR, None, message);
+    }
+}
+impl From<&str> for ParseIssue {
+    fn from(message: &str) -> Self {
+        return Self::from(message.to_owned());
+    }
+}
+///columns of subject in the line, of the whole statement if it's not there
+fn find_columns(token_line: &TokenLine, subject: Option<&Token>) -> Option<(usize, usize)> {
+    let index = subject.and_then(|s| token_line.elements.iter().position(|e| e == s));
+    if let Some(index) = index {
+        return token_line.columns.get(index).copied();
+    }
+    let first = token_line.columns.first()?;
+    let last = token_line.columns.last()?;
+    return Some((first.0, last.1));
+}
+
 impl ParserError {
-    pub fn upgrade(content: String, token_line: &TokenLine) -> Self {
-        return Self::new(token_line.line_number, token_line.org.clone(), content);
+    pub fn upgrade(content: impl Into<ParseIssue>, token_line: &TokenLine) -> Self {
+        let issue = content.into();
+        let mut err = Self::new(
+            token_line.line_number,
+            token_line.org.clone(),
+            issue.message,
+        );
+        err.code = Some(issue.code);
+        err.columns = find_columns(token_line, issue.subject.as_ref());
+        return err;
     }
     pub fn general(content: String) -> Self {
         return Self::new(0, "".to_owned(), content);
@@ -185,6 +352,21 @@ impl ProgramTree {
     pub fn get_fnc_mut(&mut self, t: FunctionId) -> &mut Function {
         return &mut self.functions[t.0];
     }
+    pub fn find_string_index(&self, t: &str) -> Option<usize> {
+        return self.strings_used.iter().position(|e| e == t);
+    }
+    pub fn get_struct(&self, t: StructId) -> &Struct {
+        return &self.structs[t.0];
+    }
+    pub fn find_struct_with_name(&self, t: &str) -> Option<&Struct> {
+        return self.structs.iter().find(|e| e.name == t);
+    }
+    pub fn find_named_constant(&self, t: &str) -> Option<&NamedConstant> {
+        return self.named_constants.iter().find(|e| e.name == t);
+    }
+    pub fn get_flag_name(&self, t: FlagId) -> &str {
+        return &self.get_fnc(t.fnc).flags[t.raw];
+    }
 
     pub fn find_global_var_with_name(&self, t: &str) -> Result<&Variable, String> {
         return self
@@ -200,14 +382,13 @@ impl ProgramTree {
         fnc.args = decl.arguments.len();
         fnc.id = FunctionId(self.functions.len());
         fnc.is_stack = decl.is_stack;
+        fnc.doc = decl.doc;
+        fnc.line = decl.line;
         if fnc.is_stack {
             self.features |= FeatureFlags::StackFunctions;
         }
         push_variables(
-            decl.arguments
-                .into_iter()
-                .map(|e| VariableDeclaration::new(e, -21))
-                .collect(),
+            decl.arguments,
             &mut fnc.locals,
             VariableType::Local(fnc.id),
             true,
@@ -227,16 +408,71 @@ impl ProgramTree {
 }
 impl VariableType {}
 
+impl Struct {
+    pub fn find_field(&self, name: &str) -> Result<usize, String> {
+        return self
+            .fields
+            .iter()
+            .position(|e| e == name)
+            .ok_or_else(|| format!("struct {} has no field {}", self.name, name));
+    }
+}
+
+impl Switch {
+    ///(lowest case, table length) when cases are dense enough for a jump table
+    pub fn table_range(&self) -> Option<(i32, i32)> {
+        if self.cases.len() < 4 {
+            return None;
+        }
+        let min = self.cases.iter().map(|e| e.0).min().unwrap(); //not empty
+        let max = self.cases.iter().map(|e| e.0).max().unwrap();
+        let len = max as i64 - min as i64 + 1;
+        if len > 2 * self.cases.len() as i64 {
+            return None;
+        }
+        return Some((min, len as i32));
+    }
+}
+
+impl ArgumentCallArg {
+    ///how deep operations are nested, 0 for anything that is not an operation
+    pub fn height(&self) -> usize {
+        if let ArgumentCallArg::Operation(op) = self {
+            return 1 + op.a.height().max(op.b.height());
+        }
+        return 0;
+    }
+}
+pub fn get_operation_temp_name(height: usize) -> String {
+    return format!("_op_tmp_{height}");
+}
+
 impl<'a> BuildingContext<'a> {
-    pub fn try_find_variable(&self, name: &str) -> Result<&Variable, String> {
+    pub fn try_find_variable(&self, name: &str) -> Result<&Variable, ParseIssue> {
         let local = self.cur_function.locals.iter().find(|e| e.name == name);
         if let Some(value) = local {
             return Ok(value);
         }
-        return self
-            .cur_tree
-            .find_global_var_with_name(name)
-            .map_err(|e| format!("neither local nor global {} found", name));
+        return self.cur_tree.find_global_var_with_name(name).map_err(|_| {
+            ParseIssue::new(
+                diagnostics::UNDEFINED_VARIABLE,
+                Some(Token::m_label(name)),
+                format!("neither local nor global {} found", name),
+            )
+        });
+    }
+    ///flags can be used above their definition, so every name gets an id on first sight
+    fn mark_flag(&mut self, label: &str, is_definition: bool) -> FlagId {
+        let raw = match self.flags.iter().position(|e| e == label) {
+            Some(raw) => raw,
+            None => {
+                self.flags.push(label.to_owned());
+                self.flags.len() - 1
+            }
+        };
+        self.flag_marks
+            .push(FlagMark::new(raw, self.cur_line.clone(), is_definition));
+        return FlagId::new(raw, self.cur_function.id);
     }
 }
 
@@ -257,12 +493,104 @@ pub fn match_exact_w_upgrade(
         .map_err(|e| ParserError::upgrade(e, token_line));
 }
 
+///length of "x", "p.x" or "p->x" at the beginning, checks only the tokens not the names
+fn place_length(tokens: &[Token]) -> Option<usize> {
+    if !matches!(tokens.first(), Some(Token::Label(_))) {
+        return None;
+    }
+    if match_exact_cond(
+        &[
+            (TokenReq::m_symbol(Dot), IndexReq::Beg(1)),
+            (TokenReq::Label, IndexReq::Beg(2)),
+        ],
+        tokens,
+    ) {
+        return Some(3);
+    }
+    if match_exact_cond(
+        &[
+            (TokenReq::m_symbol(Minus), IndexReq::Beg(1)),
+            (TokenReq::m_symbol(AngleClose), IndexReq::Beg(2)),
+            (TokenReq::Label, IndexReq::Beg(3)),
+        ],
+        tokens,
+    ) {
+        return Some(4);
+    }
+    return Some(1);
+}
+///tokens have to be exactly what place_length measured
+fn parse_place(tokens: &[Token], context: &BuildingContext) -> Result<Place, ParseIssue> {
+    let name = tokens[0].to_label().unwrap(); //checked by place_length
+    if context.cur_tree.find_named_constant(&name).is_some() {
+        return Err(format!("{name} is a constant and can't be assigned").into());
+    }
+    let var = context.try_find_variable(&name)?;
+    let tree = context.cur_tree;
+    return match (tokens.len(), var.data_type) {
+        (1, DataType::Struct(_)) => Err(format!(
+            "struct variable {name} can't be used as a value, use its fields or &{name}"
+        )
+        .into()),
+        (1, _) => Ok(Place::Variable(var.id)),
+        (3, DataType::Struct(id)) => {
+            let field = tree
+                .get_struct(id)
+                .find_field(&tokens[2].to_label().unwrap())
+                .map_err(|e| {
+                    ParseIssue::new(diagnostics::UNDEFINED_FIELD, Some(tokens[2].clone()), e)
+                })?;
+            Ok(Place::Field(var.id, field))
+        }
+        (3, _) => Err(format!("{name} is not a struct, '.' can't be used on it").into()),
+        (_, DataType::StructPtr(id)) => {
+            let field = tree
+                .get_struct(id)
+                .find_field(&tokens[3].to_label().unwrap())
+                .map_err(|e| {
+                    ParseIssue::new(diagnostics::UNDEFINED_FIELD, Some(tokens[3].clone()), e)
+                })?;
+            Ok(Place::PtrField(var.id, field))
+        }
+        (_, _) => Err(format!("{name} is not a struct pointer, '->' can't be used on it").into()),
+    };
+}
+
+///arguments are read only unless declared with "mut"
+fn check_writable(place: &Place, context: &BuildingContext) -> Result<(), ParseIssue> {
+    if let Place::Variable(VariableId {
+        raw,
+        kind: VariableType::Local(_),
+    }) = place
+    {
+        let var = &context.cur_function.locals[*raw];
+        if var.read_only {
+            return Err(ParseIssue::new(
+                diagnostics::READ_ONLY_ARGUMENT,
+                Some(Token::m_label(&var.name)),
+                format!(
+                    "argument {} of function {} is read only, declare it as 'mut {}' to assign to it",
+                    var.name, context.cur_function.name, var.name
+                ),
+            ));
+        }
+    }
+    return Ok(());
+}
+
+fn unclosed_string_issue(literal: &Token) -> ParseIssue {
+    return ParseIssue::new(
+        diagnostics::UNCLOSED_STRING,
+        Some(literal.clone()),
+        "String literal is missing closing quote".to_owned(),
+    );
+}
 ///works for args NOT ending in ')'
 ///for args ending with ')' it will skip it at the end
 fn parse_argument_next<'a>(
     tokens: &'a [Token],
-    context: &BuildingContext,
-) -> Result<Option<(ArgumentCallArg, &'a [Token])>, String> {
+    context: &mut BuildingContext,
+) -> Result<Option<(ArgumentCallArg, &'a [Token])>, ParseIssue> {
     if tokens.len() == 0 {
         return Ok(None);
     }
@@ -270,12 +598,31 @@ fn parse_argument_next<'a>(
         return Ok(None);
     }
     match &tokens[0] {
-        Token::Label(label) => {
-            return Ok(Some((
-                ArgumentCallArg::Reference(context.try_find_variable(&label)?.id),
-                &tokens[1..],
-            )));
+        Token::Label(_) => {
+            let len = place_length(tokens).unwrap(); //starts with label so always some
+            let name = tokens[0].to_label().unwrap();
+            if let Some(constant) = context.cur_tree.find_named_constant(&name) {
+                if len != 1 {
+                    return Err(format!("{name} is a constant, it has no fields").into());
+                }
+                return Ok(Some((
+                    ArgumentCallArg::Literal(constant.value),
+                    &tokens[1..],
+                )));
+            }
+            let arg = match parse_place(&tokens[..len], context)? {
+                Place::Variable(id) => ArgumentCallArg::Reference(id),
+                Place::Field(id, field) => ArgumentCallArg::Field(id, field),
+                Place::PtrField(id, field) => ArgumentCallArg::PtrField(id, field),
+            };
+            return Ok(Some((arg, &tokens[len..])));
         } //TODO this
+        Token::Symbol(Symbol::ParenthesisOpen) => {
+            let close = find_matching_parenthesis(tokens)
+                .ok_or_else(|| "Parenthesis was not closed".to_owned())?;
+            let expression = parse_expression(&tokens[1..close], context)?;
+            return Ok(Some((expression, &tokens[close + 1..])));
+        }
         Token::Symbol(Symbol::Asterix | Symbol::Ampersand | Symbol::Minus) => {
             //tokens.len()!=0
             if tokens.len() == 1 || !matches!(tokens[1], Token::Label(_)) {
@@ -283,6 +630,9 @@ fn parse_argument_next<'a>(
             }
             let label = tokens[1].to_label().unwrap(); //safe unwrap
             let symbol = Symbol::try_from(tokens[0].clone()).unwrap(); //safe unwrap
+            if symbol != Symbol::Minus && context.cur_tree.find_named_constant(&label).is_some() {
+                return Err(format!("{label} is a constant, it has no address").into());
+            }
             return match symbol {
                 Symbol::Asterix => Ok(Some((
                     ArgumentCallArg::Deref(context.try_find_variable(&label)?.id),
@@ -294,7 +644,7 @@ fn parse_argument_next<'a>(
                 ))),
 
                 Symbol::Minus => Ok(Some((
-                    ArgumentCallArg::Flag(label, context.cur_function.id),
+                    ArgumentCallArg::Flag(context.mark_flag(&label, false)),
                     &tokens[2..],
                 ))),
                 _ => panic!("logic error"),
@@ -303,45 +653,210 @@ fn parse_argument_next<'a>(
         Token::Number(num) => {
             return Ok(Some((ArgumentCallArg::Literal(*num), &tokens[1..])));
         }
-        _ => {
-            return Err(format!(
-                "Incorrect token while parsing arguments {:?}",
-                tokens[0]
-            ))
+        Token::Str(StrLiteral { closed: false, .. }) => {
+            return Err(unclosed_string_issue(&tokens[0]));
+        }
+        Token::Str(StrLiteral { content, .. }) => {
+            return Ok(Some((ArgumentCallArg::Str(content.clone()), &tokens[1..])));
+        }
+        _ => return Err(format!("Incorrect token while parsing arguments {:?}", tokens[0]).into()),
+    }
+}
+///assumes tokens[0]=='('
+fn find_matching_parenthesis(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, el) in tokens.iter().enumerate() {
+        match el {
+            Token::Symbol(ParenthesisOpen) => depth += 1,
+            Token::Symbol(ParenthesisClose) => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+    return None;
+}
+fn to_operation_kind(t: &Token) -> Option<OperationKind> {
+    return match t {
+        Token::Symbol(Asterix) => Some(OperationKind::Mul),
+        Token::Symbol(Slash) => Some(OperationKind::Div),
+        Token::Symbol(Percent) => Some(OperationKind::Mod),
+        _ => None,
+    };
+}
+///value followed by any number of "OP value", evaluated from left to right
+///returns tokens after the last value
+fn parse_operations<'a>(
+    tokens: &'a [Token],
+    context: &mut BuildingContext,
+) -> Result<(ArgumentCallArg, &'a [Token]), ParseIssue> {
+    let (mut result, mut tokens) = parse_argument_next(tokens, context)?
+        .ok_or_else(|| "Expected value in expression".to_owned())?;
+    while let Some(kind) = tokens.first().and_then(to_operation_kind) {
+        let (b, rest) = parse_argument_next(&tokens[1..], context)?
+            .ok_or_else(|| "Expected value after operator".to_owned())?;
+        result = ArgumentCallArg::Operation(Box::new(Operation::new(kind, result, b)));
+        tokens = rest;
+    }
+    return Ok((result, tokens));
+}
+///whole slice has to be consumed
+fn parse_expression(
+    tokens: &[Token],
+    context: &mut BuildingContext,
+) -> Result<ArgumentCallArg, ParseIssue> {
+    let (result, rest) = parse_operations(tokens, context)?;
+    if !rest.is_empty() {
+        return Err(format!(
+            "Expected operator (* / %) in expression, found {:?}",
+            rest[0]
+        )
+        .into());
+    }
+    return Ok(result);
+}
+///remembers everything the compiler has to emit for the argument (constants, strings, helpers)
+fn register_argument(arg: &ArgumentCallArg, context: &mut BuildingContext) {
+    match arg {
+        ArgumentCallArg::Literal(v) => {
+            context.uses.constants.insert(*v);
+        }
+        ArgumentCallArg::Str(content) if !context.uses.strings.contains(content) => {
+            context.uses.strings.push(content.clone());
+        }
+        ArgumentCallArg::GetAddress(id) => {
+            context.uses.addresses.insert(*id);
+        }
+        ArgumentCallArg::PtrField(_, field) => {
+            context.uses.constants.insert(*field as i32);
+            context.uses.features |= FeatureFlags::StructPointers;
+        }
+        ArgumentCallArg::Operation(op) => {
+            context.uses.features |= match op.kind {
+                OperationKind::Mul => FeatureFlags::Multiplication,
+                OperationKind::Div | OperationKind::Mod => FeatureFlags::Division,
+            };
+            if op.b.height() > 0 {
+                context.uses.operation_temps = context.uses.operation_temps.max(arg.height());
+            }
+            register_argument(&op.a, context);
+            register_argument(&op.b, context);
+        }
+        _ => {}
+    }
+}
+fn register_place(place: &Place, context: &mut BuildingContext) {
+    if let Place::PtrField(_, field) = place {
+        context.uses.constants.insert(*field as i32);
+        context.uses.features |= FeatureFlags::StructPointers;
+    }
+}
+///at the first value separated with space
+fn mixed_separators_issue(value: &Token) -> ParseIssue {
+    return ParseIssue::new(
+        diagnostics::MIXED_SEPARATORS,
+        Some(value.clone()),
+        "Separate all values with commas or all with spaces, not both".to_owned(),
+    );
+}
+const OPERATION_NEEDS_PARENTHESES: &str =
+    "Wrap operations in parentheses like (a * b) or separate values with commas";
+///splits at commas outside of parentheses, None when there are no commas
+fn split_at_commas(tokens: &[Token]) -> Result<Option<Vec<&[Token]>>, ParseIssue> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut beg = 0;
+    for (i, el) in tokens.iter().enumerate() {
+        match el {
+            Token::Symbol(ParenthesisOpen) => depth += 1,
+            Token::Symbol(ParenthesisClose) => depth -= 1,
+            Token::Symbol(Comma) if depth <= 0 => {
+                parts.push(&tokens[beg..i]);
+                beg = i + 1;
+            }
+            _ => {}
         }
     }
+    if parts.is_empty() {
+        return Ok(None);
+    }
+    parts.push(&tokens[beg..]);
+    if parts.iter().any(|e| e.is_empty()) {
+        return Err("Expected value before and after every comma".into());
+    }
+    return Ok(Some(parts));
 }
 //assumes tokens[0]==label
 fn parse_call_arguments(
     tokens: &[Token],
     context: &mut BuildingContext,
-) -> Result<Vec<ArgumentCallArg>, String> {
-    let result = utility::build_step_simple(&tokens[0..], |t| parse_argument_next(t, context))?;
-    for el in &result {
-        if let ArgumentCallArg::Literal(v) = el {
-            context.constants.insert(*v);
+) -> Result<Vec<ArgumentCallArg>, ParseIssue> {
+    //closing parenthesis of the call
+    let inside = match tokens.last() {
+        Some(Token::Symbol(ParenthesisClose)) => &tokens[..tokens.len() - 1],
+        _ => tokens,
+    };
+    let mut result = Vec::new();
+    match split_at_commas(inside)? {
+        //"a *b" is a and *b like it always was, other operators need parentheses
+        None => {
+            let mut rest = inside;
+            while let Some((arg, next)) = parse_argument_next(rest, context)? {
+                let deref = matches!(next, [Token::Symbol(Asterix), Token::Label(_), ..]);
+                if !deref && next.first().and_then(to_operation_kind).is_some() {
+                    return Err(ParseIssue::new(
+                        diagnostics::MISSING_PARENTHESES,
+                        Some(next[0].clone()),
+                        OPERATION_NEEDS_PARENTHESES.to_owned(),
+                    ));
+                }
+                result.push(arg);
+                rest = next;
+            }
+            if !rest.is_empty() {
+                return Err(format!("Incorrect argument {:?}", rest).into());
+            }
+        }
+        Some(parts) => {
+            for part in parts {
+                let (arg, rest) = parse_operations(part, context)?;
+                if !rest.is_empty() {
+                    return Err(mixed_separators_issue(&rest[0]));
+                }
+                result.push(arg);
+            }
         }
     }
+    for el in &result {
+        register_argument(el, context);
+    }
+    return Ok(result);
+}
+///for places that take exactly one value, there operators don't need parenthesis
+fn parse_single_value(
+    tokens: &[Token],
+    context: &mut BuildingContext,
+) -> Result<ArgumentCallArg, ParseIssue> {
+    let result = parse_expression(tokens, context)?;
+    register_argument(&result, context);
     return Ok(result);
 }
 
 fn parse_function_call_if_present(
     tokens: &[Token],
     context: &mut BuildingContext,
-) -> Result<Option<FunctionCall>, String> {
+) -> Result<Option<FunctionCall>, ParseIssue> {
     let mut tokens = tokens;
     let mut assignment = None;
 
-    if match_exact_cond(
-        &[
-            (TokenReq::Label, IndexReq::Next),
-            (TokenReq::m_symbol(Equal), IndexReq::Next),
-        ],
-        tokens,
-    ) {
-        let assigment_name = tokens[0].to_label().unwrap();
-        assignment = Some(context.try_find_variable(&assigment_name)?.id);
-        tokens = &tokens[2..];
+    let place_len = place_length(tokens).unwrap_or(0);
+    if place_len > 0 && tokens.get(place_len) == Some(&Token::Symbol(Equal)) {
+        let place = parse_place(&tokens[..place_len], context)?;
+        check_writable(&place, context)?;
+        register_place(&place, context);
+        assignment = Some(place);
+        tokens = &tokens[place_len + 1..];
     }
 
     if !match_exact_cond(
@@ -360,19 +875,29 @@ fn parse_function_call_if_present(
     let function = context
         .cur_tree
         .find_fnc_with_name(label.as_str())
-        .ok_or_else(|| format!("function {} not found", label))?;
+        .ok_or_else(|| {
+            ParseIssue::new(
+                diagnostics::UNDEFINED_FUNCTION,
+                Some(tokens[0].clone()),
+                format!("function {} not found", label),
+            )
+        })?;
 
     let arguments_got = parse_call_arguments(&tokens[2..], context)?;
 
     if arguments_got.len() != function.args {
-        return Err(format!(
-            "Function takes {} but {} were given",
-            function.args,
-            arguments_got.len()
+        return Err(ParseIssue::new(
+            diagnostics::ARGUMENT_COUNT,
+            Some(tokens[0].clone()),
+            format!(
+                "Function takes {} but {} were given",
+                function.args,
+                arguments_got.len()
+            ),
         ));
     }
     if function.is_stack && (!context.cur_function.is_stack && !is_main(context.cur_function)) {
-        return Err(format!("Can't 'call {} from {}. Stack function can be called only from other stack functions or from main",function.name,context.cur_function.name));
+        return Err(format!("Can't 'call {} from {}. Stack function can be called only from other stack functions or from main",function.name,context.cur_function.name).into());
     }
     return Ok(Some(FunctionCall::new(
         function.id,
@@ -382,27 +907,133 @@ fn parse_function_call_if_present(
     )));
 }
 
-fn parse_inline_if_present(tokens: &[Token]) -> Result<Option<Inline>, String> {
-    return match_exact_ok(
+const MARIE_INSTRUCTIONS: [&str; 18] = [
+    "load", "store", "add", "subt", "input", "output", "halt", "skipcond", "jump", "clear", "addi",
+    "jumpi", "loadi", "storei", "jns", "dec", "hex", "oct",
+];
+const MARIE_NO_OPERAND: [&str; 4] = ["input", "output", "halt", "clear"];
+
+///"beg, clear / comment" is fine, label before ',' is optional
+fn check_inline_instruction(text: &str) -> Result<(), String> {
+    let code = text.split('/').next().unwrap(); //split always yields at least one
+    let instruction = code.split_once(',').map(|e| e.1).unwrap_or(code);
+    let mut words = instruction.split_whitespace();
+    let mnemonic = words
+        .next()
+        .ok_or_else(|| "inline assembly without instruction".to_owned())?;
+    let lower = mnemonic.to_lowercase();
+    if !MARIE_INSTRUCTIONS.contains(&lower.as_str()) {
+        return Err(format!("{mnemonic} is not a MARIE instruction"));
+    }
+    let operands = words.count();
+    if MARIE_NO_OPERAND.contains(&lower.as_str()) {
+        if operands != 0 {
+            return Err(format!("{mnemonic} doesn't take an operand"));
+        }
+    } else if operands != 1 {
+        return Err(format!("{mnemonic} takes exactly one operand"));
+    }
+    return Ok(());
+}
+///inside of {}: "x", "p.x", "CONST" or "@flag"
+fn parse_interpolation(
+    inner: &str,
+    context: &mut BuildingContext,
+) -> Result<InlinePart, ParseIssue> {
+    let inner = inner.trim();
+    if let Some(flag) = inner.strip_prefix('@') {
+        let tokens = tokenize_line(flag);
+        match_exact(
+            &[
+                (TokenReq::Label, IndexReq::Next),
+                (TokenReq::None, IndexReq::Next),
+            ],
+            &tokens,
+        )?;
+        let label = tokens[0].to_label().unwrap();
+        return Ok(InlinePart::Flag(context.mark_flag(&label, false)));
+    }
+
+    let tokens = tokenize_line(inner);
+    if place_length(&tokens) != Some(tokens.len()) {
+        return Err(format!("{{{inner}}} is neither a variable nor a flag").into());
+    }
+    let name = tokens[0].to_label().unwrap(); //checked by place_length
+    if let Some(constant) = context.cur_tree.find_named_constant(&name) {
+        if tokens.len() != 1 {
+            return Err(format!("{name} is a constant, it has no fields").into());
+        }
+        context.uses.constants.insert(constant.value);
+        return Ok(InlinePart::Constant(constant.value));
+    }
+    return match parse_place(&tokens, context)? {
+        Place::Variable(id) => Ok(InlinePart::Variable(id)),
+        Place::Field(id, field) => Ok(InlinePart::Field(id, field)),
+        Place::PtrField(..) => Err(format!(
+            "{{{inner}}} can't be used in inline assembly, use {{{name}}} with loadi/storei"
+        )
+        .into()),
+    };
+}
+///"load {x}" -> [Text("load "), Variable(x)]
+fn parse_inline(text: &str, context: &mut BuildingContext) -> Result<Vec<InlinePart>, ParseIssue> {
+    let mut parts = Vec::new();
+    let mut checked = String::new(); //interpolations replaced by a placeholder operand
+    let mut rest = text.trim();
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .map(|e| e + open)
+            .ok_or_else(|| "'{' in inline assembly was not closed".to_owned())?;
+        if open > 0 {
+            parts.push(InlinePart::Text(rest[..open].to_owned()));
+        }
+        parts.push(parse_interpolation(&rest[open + 1..close], context)?);
+        checked += &rest[..open];
+        checked += "_";
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(InlinePart::Text(rest.to_owned()));
+    }
+    checked += rest;
+    check_inline_instruction(&checked)
+        .map_err(|e| ParseIssue::new(diagnostics::INVALID_INLINE, None, e))?;
+    return Ok(parts);
+}
+fn parse_inline_if_present(
+    tokens: &[Token],
+    context: &mut BuildingContext,
+) -> Result<Option<Vec<InlinePart>>, ParseIssue> {
+    if !match_exact_cond(
         &[
             (TokenReq::Inline, IndexReq::Next),
             (TokenReq::None, IndexReq::Next),
         ],
         tokens,
-    )
-    .map(|e| Inline::try_from(tokens[0].clone()).unwrap()) //this unwrap should never fail
-    .pack_in_result(); //(for now) never fails, either not an inline or correct inline
+    ) {
+        return Ok(None);
+    }
+    let inline = Inline::try_from(tokens[0].clone()).unwrap(); //checked above
+    return parse_inline(&inline.0, context).map(Some);
 }
 fn parse_simple(
     tokens: &[Token],
     context: &mut BuildingContext,
-) -> Result<Option<Statement>, String> {
+) -> Result<Option<Statement>, ParseIssue> {
     if tokens.len() == 0 {
         return Ok(None);
     }
+    //it took the rest of the line, nothing after it makes sense
+    let unclosed = tokens
+        .iter()
+        .find(|e| matches!(e, Token::Str(StrLiteral { closed: false, .. })));
+    if let Some(literal) = unclosed {
+        return Err(unclosed_string_issue(literal));
+    }
 
-    return parse_inline_if_present(tokens)
-        .deep_map(|e| Statement::Inline(e.0))
+    return parse_inline_if_present(tokens, context)
+        .deep_map(Statement::Inline)
         .other_else(|| {
             parse_assignment_if_present(tokens, context).deep_map(|e| Statement::Assignment(e))
         })
@@ -439,30 +1070,63 @@ fn parse_simple(
 fn parse_assignment_if_present(
     tokens: &[Token],
     context: &mut BuildingContext,
-) -> Result<Option<Assignment>, String> {
-    if !match_exact_cond(
-        &[
-            (TokenReq::Label, IndexReq::Next),
-            (TokenReq::m_symbol(Colon), IndexReq::Next),
-            (TokenReq::m_symbol(Equal), IndexReq::Next),
-        ],
-        tokens,
-    ) {
+) -> Result<Option<Assignment>, ParseIssue> {
+    let place_len = place_length(tokens).unwrap_or(0);
+    if place_len == 0
+        || !match_exact_cond(
+            &[
+                (TokenReq::m_symbol(Colon), IndexReq::Beg(place_len)),
+                (TokenReq::m_symbol(Equal), IndexReq::Beg(place_len + 1)),
+            ],
+            tokens,
+        )
+    {
         //not an assignment
         return Ok(None);
     }
-    let left_text = tokens[0].to_label().unwrap();
-    let left = context.try_find_variable(&left_text)?.id;
+    let left = parse_place(&tokens[..place_len], context)?;
+    check_writable(&left, context)?;
+    register_place(&left, context);
 
-    let arguments = parse_call_arguments(&tokens[3..], context)?;
-    if arguments.len() != 1 {
-        return Err("Assignment requires exactly one argument at the right side".to_owned());
+    if tokens.len() == place_len + 2 {
+        return Err("Assignment requires exactly one argument at the right side".into());
     }
-    let right = arguments[0].clone();
+    let right = parse_single_value(&tokens[place_len + 2..], context)?;
 
     return Ok(Some(Assignment::new(left, right)));
 }
 
+///index of the line after statement that failed to parse at i,
+///blocks and else arms following it are skipped too
+fn skip_statement(lines: &[TokenLine], i: usize) -> usize {
+    let mut i = i + 1;
+    while i < lines.len() {
+        match lines[i].elements.as_slice() {
+            [Token::Symbol(BraceOpen)] => {
+                let mut depth = 0;
+                while i < lines.len() {
+                    match lines[i].elements.as_slice() {
+                        [Token::Symbol(BraceOpen)] => depth += 1,
+                        [Token::Symbol(BraceClose)] => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            [Token::Symbol(Dot), Token::Label(label), ..]
+                if matches!(label.as_str(), "else" | "elif" | "noelse") =>
+            {
+                i += 1
+            }
+            _ => return i,
+        }
+    }
+    return i;
+}
+
 fn parse_internal(
     lines: &[TokenLine],
     context: &mut BuildingContext,
@@ -486,24 +1150,35 @@ fn parse_internal(
             return SingleStepH::deliver(statements, i + 1);
         }
 
-        let simple_maybe =
-            parse_simple(&line.elements, context).map_err(|e| ParserError::upgrade(e, &line))?;
+        context.cur_line = line.clone();
+        let simple_maybe = parse_simple(&line.elements, context);
 
-        if let Some(simple) = simple_maybe {
-            statements.push(simple);
-            i += 1;
-            continue;
+        match simple_maybe {
+            Ok(Some(simple)) => {
+                statements.push(LineStatement::new(simple, line.line_number));
+                i += 1;
+                continue;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                context.errors.push(ParserError::upgrade(err, line));
+                i = skip_statement(lines, i);
+                continue;
+            }
         }
 
-        let adv_maybe = parse_advanced(&lines[i..], context)?;
-        if let Some(adv) = adv_maybe {
-            statements.push(adv.apply(&mut i));
-            continue;
+        match parse_advanced(&lines[i..], context) {
+            Ok(Some(adv)) => {
+                statements.push(LineStatement::new(adv.apply(&mut i), line.line_number));
+                continue;
+            }
+            Ok(None) => context.errors.push(ParserError::upgrade(
+                format!("Expected correct statement, found {:?}", line.elements),
+                line,
+            )),
+            Err(err) => context.errors.push(err),
         }
-        return Err(ParserError::upgrade(
-            format!("Expected correct statement, found {:?}", line.elements),
-            line,
-        ));
+        i = skip_statement(lines, i);
     }
     return Err(ParserError::upgrade(
         "Block was not closed".to_owned(),
@@ -515,7 +1190,6 @@ fn parse_advanced<'a>(
     tokens: &'a [TokenLine],
     context: &mut BuildingContext,
 ) -> Step<Statement, ParserError> {
-    let mut tokens = tokens;
     if tokens.len() == 0 {
         return StepH::end();
     }
@@ -539,16 +1213,13 @@ fn parse_advanced<'a>(
     return match name.as_str() {
         "ret" => {
             let len = tokens[0].elements.len();
-            let args =
-                parse_call_arguments(&tokens[0].elements[3..len - 1], context).map_err(upg_0)?;
-
-            if args.len() != 1 {
+            if len == 4 {
                 return Err(upg_0("Return accepts one or 0 arguments".to_owned()));
             }
-            let mut id = None;
-            if args.len() == 1 {
-                id = Some(args[0].clone());
-            }
+            let id = Some(
+                parse_single_value(&tokens[0].elements[3..len - 1], context)
+                    .map_err(|e| ParserError::upgrade(e, &tokens[0]))?,
+            );
 
             return StepH::deliver(Statement::Ret(Ret::new(id, context.cur_function.id)), 1);
         }
@@ -562,79 +1233,480 @@ fn parse_advanced<'a>(
                 return Err(upg_0("Flag accepts exactly one arguments".to_owned()));
             }
 
-            return StepH::deliver(Statement::Flag(Flag::new(arg)), 1);
+            let id = context.mark_flag(&arg, true);
+            return StepH::deliver(Statement::Flag(Flag::new(id)), 1);
         }
-        "if" => {
-            let cond_kind = tokens[0].elements[3]
-                .to_cond()
-                .ok_or_else(|| upg_0("If must start with condition kind".to_owned()))?;
+        "goto" => {
+            match_exact(
+                &[
+                    (TokenReq::Label, IndexReq::Beg(3)),
+                    (TokenReq::m_symbol(ParenthesisClose), IndexReq::Next),
+                    (TokenReq::None, IndexReq::Next),
+                ],
+                &tokens[0].elements,
+            )
+            .map_err(|_| upg_0("goto accepts exactly one flag name".to_owned()))?;
+            let arg = tokens[0].elements[3].to_label().unwrap(); //checked above
 
-            let arguments =
-                parse_call_arguments(&&tokens[0].elements[4..], context).map_err(|e| upg_0(e))?;
+            let id = context.mark_flag(&arg, false);
+            return StepH::deliver(Statement::Goto(id), 1);
+        }
+        "switch" => {
+            let len = tokens[0].elements.len();
+            if len == 4 {
+                return Err(upg_0("Switch accepts exactly one argument".to_owned()));
+            }
+            let value = parse_single_value(&tokens[0].elements[3..len - 1], context)
+                .map_err(|e| ParserError::upgrade(e, &tokens[0]))?;
+
+            let mut i = 1;
+            let switch = parse_switch_arms(&tokens[1..], value, context)?.apply(&mut i);
+            //values the compiler compares with, see compile_advance
+            match switch.table_range() {
+                Some((min, len)) => {
+                    context.uses.constants.insert(min);
+                    context.uses.constants.insert(len);
+                }
+                None => {
+                    for el in &switch.cases {
+                        context.uses.constants.insert(el.0);
+                    }
+                }
+            }
+            return StepH::deliver(Statement::Switch(switch), i);
+        }
+        "if" => {
+            let mut i = 0;
+            let statement = parse_if(tokens, context)?.apply(&mut i);
+            return StepH::deliver(Statement::If(statement), i);
+        }
+        _ => StepH::end(),
+    };
+}
 
-            let a1 = &arguments[0];
-            let a2 = &arguments[1];
+///".if(COND a b)" or ".elif(COND a b)" line, its block and optional else part:
+///".else" with a block, ".noelse", ".elif" that continues the chain or nothing
+fn parse_if(lines: &[TokenLine], context: &mut BuildingContext) -> SingleStep<If, ParserError> {
+    let mut lines = lines;
+    let first = &lines[0];
+    let upg_0 = |t| ParserError::upgrade(t, first);
 
-            let mut i = 0;
+    match_exact_w_upgrade(
+        &[
+            (TokenReq::m_symbol(Dot), IndexReq::Next),
+            (TokenReq::Label, IndexReq::Next),
+            (TokenReq::m_symbol(ParenthesisOpen), IndexReq::Next),
+            (TokenReq::Any, IndexReq::Next),
+            (TokenReq::m_symbol(ParenthesisClose), IndexReq::End(0)),
+        ],
+        first,
+    )?;
+    let cond_kind = first.elements[3]
+        .to_cond()
+        .ok_or_else(|| upg_0("If must start with condition kind".to_owned()))?;
+
+    let arguments = parse_call_arguments(&first.elements[4..], context)
+        .map_err(|e| ParserError::upgrade(e, first))?;
+    if arguments.len() != 2 {
+        return Err(upg_0("If compares exactly two values".to_owned()));
+    }
 
-            push_slice(&mut tokens, 1, &mut i);
+    let mut i = 0;
+    push_slice(&mut lines, 1, &mut i);
 
-            let block_if = parse_internal(&tokens, context)?.apply_a(&mut tokens, &mut i);
+    let block_if = parse_internal(lines, context)?.apply_a(&mut lines, &mut i);
 
+    let else_kind = match lines.first().map(|e| e.elements.as_slice()) {
+        Some([Token::Symbol(Dot), Token::Label(label), ..]) => label.as_str(),
+        _ => "",
+    };
+    let block_else = match else_kind {
+        "else" | "noelse" => {
             match_exact_w_upgrade(
                 &[
                     (TokenReq::m_symbol(Dot), IndexReq::Next),
-                    (
-                        TokenReq::Either(Token::m_label("else"), Token::m_label("noelse")),
-                        IndexReq::Next,
-                    ),
+                    (TokenReq::Label, IndexReq::Next),
                     (TokenReq::None, IndexReq::Next),
                 ],
-                &tokens[0],
+                &lines[0],
             )?;
-            let is_else_included = tokens[0].elements[1].is_exact_label("else"); // safe indexing
+            push_slice(&mut lines, 1, &mut i);
+            match else_kind {
+                "else" => Some(parse_internal(lines, context)?.apply_a(&mut lines, &mut i)),
+                _ => None, //kept for older code, same as no else
+            }
+        }
+        //".elif" is an if inside of else
+        "elif" => {
+            let elif_line = lines[0].line_number;
+            let nested = parse_if(lines, context)?.apply_a(&mut lines, &mut i);
+            Some(vec![LineStatement::new(Statement::If(nested), elif_line)])
+        }
+        _ => None,
+    };
+
+    let mut arguments = arguments.into_iter();
+    return SingleStepH::deliver(
+        If {
+            a: arguments.next().unwrap(), //checked length
+            b: arguments.next().unwrap(),
+            if_true: block_if,
+            if_false: block_else,
+            cond: cond_kind,
+        },
+        i,
+    );
+}
+
+///"{", any number of ".case(N)" arms, optional ".default()" arm as the last one and "}"
+///every arm is followed by its block
+fn parse_switch_arms(
+    lines: &[TokenLine],
+    value: ArgumentCallArg,
+    context: &mut BuildingContext,
+) -> SingleStep<Switch, ParserError> {
+    let mut lines = lines;
+    let mut i = 0;
+    let mut switch = Switch::new(value, Vec::new(), None);
+
+    let first = lines.first().unwrap_or(&TokenLine::none()).clone();
+    match_upgrade_symbol_only(&first, BraceOpen)?;
+    push_slice(&mut lines, 1, &mut i);
 
-            //ignore dot
-            push_slice(&mut tokens, 1, &mut i);
+    while !lines.is_empty() {
+        let line = lines[0].clone();
+        let upg = |t| ParserError::upgrade(t, &line);
+        if match_exact_cond(
+            &[
+                (TokenReq::m_symbol(BraceClose), IndexReq::Next),
+                (TokenReq::None, IndexReq::Next),
+            ],
+            &line.elements,
+        ) {
+            return SingleStepH::deliver(switch, i + 1);
+        }
+        if switch.default.is_some() {
+            return Err(upg(".default() has to be the last arm of switch".to_owned()));
+        }
 
-            let mut block_else = None;
-            if is_else_included {
-                block_else = Some(parse_internal(&tokens, context)?.apply_a(&mut tokens, &mut i));
+        if match_exact_cond(
+            &[
+                (TokenReq::m_symbol(Dot), IndexReq::Next),
+                (TokenReq::m_label("case"), IndexReq::Next),
+                (TokenReq::m_symbol(ParenthesisOpen), IndexReq::Next),
+                (TokenReq::Any, IndexReq::Next),
+                (TokenReq::m_symbol(ParenthesisClose), IndexReq::Next),
+                (TokenReq::None, IndexReq::Next),
+            ],
+            &line.elements,
+        ) {
+            let case = parse_constant_value(&line.elements[3], context.cur_tree)
+                .map_err(|e| ParserError::upgrade(e, &line))?;
+            if case < i16::MIN as i32 || case > i16::MAX as i32 {
+                let issue = ParseIssue::new(
+                    diagnostics::PARSE_ERROR,
+                    Some(line.elements[3].clone()),
+                    format!("case {case} doesn't fit in a word, it can be at most 32767"),
+                );
+                return Err(ParserError::upgrade(issue, &line));
+            }
+            if switch.cases.iter().any(|e| e.0 == case) {
+                return Err(upg(format!(
+                    "case {case} is already handled by this switch"
+                )));
             }
+            push_slice(&mut lines, 1, &mut i);
+            let block = parse_internal(lines, context)?.apply_a(&mut lines, &mut i);
+            switch.cases.push((case, block));
+        } else if match_exact_cond(
+            &[
+                (TokenReq::m_symbol(Dot), IndexReq::Next),
+                (TokenReq::m_label("default"), IndexReq::Next),
+                (TokenReq::m_symbol(ParenthesisOpen), IndexReq::Next),
+                (TokenReq::m_symbol(ParenthesisClose), IndexReq::Next),
+                (TokenReq::None, IndexReq::Next),
+            ],
+            &line.elements,
+        ) {
+            push_slice(&mut lines, 1, &mut i);
+            switch.default = Some(parse_internal(lines, context)?.apply_a(&mut lines, &mut i));
+        } else {
+            return Err(upg(format!(
+                "Expected .case(N), .default() or }} in switch, found {:?}",
+                line.elements
+            )));
+        }
+    }
+    return Err(ParserError::upgrade(
+        "Switch was not closed".to_owned(),
+        &first,
+    ));
+}
 
-            return StepH::deliver(
-                Statement::If(If {
-                    a: a1.clone(),
-                    b: a2.clone(),
-                    if_true: block_if,
-                    if_false: block_else,
-                    cond: cond_kind,
-                }),
-                i,
-            );
+///"point" or "ptr<point>"
+pub fn parse_data_type(tokens: &[Token], tree: &ProgramTree) -> Result<DataType, ParseIssue> {
+    let find_struct = |t: &Token| {
+        let name = t.to_label().unwrap(); //checked by the pattern
+        return tree
+            .find_struct_with_name(&name)
+            .map(|e| e.id)
+            .ok_or_else(|| {
+                ParseIssue::new(
+                    diagnostics::UNDEFINED_STRUCT,
+                    Some(t.clone()),
+                    format!("struct {name} not found"),
+                )
+            });
+    };
+    if match_exact_cond(
+        &[
+            (TokenReq::m_label("ptr"), IndexReq::Next),
+            (TokenReq::m_symbol(AngleOpen), IndexReq::Next),
+            (TokenReq::Label, IndexReq::Next),
+            (TokenReq::m_symbol(AngleClose), IndexReq::Next),
+            (TokenReq::None, IndexReq::Next),
+        ],
+        tokens,
+    ) {
+        return Ok(DataType::StructPtr(find_struct(&tokens[2])?));
+    }
+    match_exact(
+        &[
+            (TokenReq::Label, IndexReq::Next),
+            (TokenReq::None, IndexReq::Next),
+        ],
+        tokens,
+    )?;
+    match tokens[0].to_label().unwrap().as_str() {
+        "int" => return Ok(DataType::Int),
+        "ptr" => return Ok(DataType::Ptr),
+        _ => return Ok(DataType::Struct(find_struct(&tokens[0])?)),
+    }
+}
+///"x: point" or "x: ptr<point> = 0"
+fn parse_typed_variable_decl(
+    tokens: &[Token],
+    tree: &ProgramTree,
+) -> Result<VariableDeclaration, ParseIssue> {
+    let name = tokens[0].to_label().unwrap(); //checked by caller
+    let equal_index = tokens
+        .iter()
+        .position(|e| *e == Token::Symbol(Equal))
+        .unwrap_or(tokens.len());
+    let data_type = parse_data_type(&tokens[2..equal_index], tree)?;
+    let is_struct = matches!(data_type, DataType::Struct(_));
+
+    let mut decl = if equal_index == tokens.len() {
+        if !is_struct {
+            return Err(format!("variable {name} requires start value").into());
         }
-        _ => StepH::end(),
+        VariableDeclaration::new(name, 0)
+    } else {
+        if is_struct {
+            return Err(format!("struct variable {name} can't have start value").into());
+        }
+        match_exact(
+            &[
+                (TokenReq::Any, IndexReq::Next),
+                (TokenReq::None, IndexReq::Next),
+            ],
+            &tokens[equal_index + 1..],
+        )?;
+        VariableDeclaration::new(name, parse_constant_value(&tokens[equal_index + 1], tree)?)
     };
+    decl.data_type = data_type;
+    return Ok(decl);
 }
-
-pub fn parse_variable_decl(tokens: &[Token]) -> Result<VariableDeclaration, String> {
+///number or name of an already declared constant
+fn parse_constant_value(token: &Token, tree: &ProgramTree) -> Result<i32, ParseIssue> {
+    return match token {
+        Token::Number(num) => Ok(*num),
+        Token::Label(name) => tree
+            .find_named_constant(name)
+            .map(|e| e.value)
+            .ok_or_else(|| {
+                ParseIssue::new(
+                    diagnostics::UNDEFINED_VARIABLE,
+                    Some(token.clone()),
+                    format!("constant {name} not found"),
+                )
+            }),
+        _ => Err(format!("Expected number or constant, found {:?}", token).into()),
+    };
+}
+fn check_not_constant(name: &str, tree: &ProgramTree) -> Result<(), ParseIssue> {
+    if tree.find_named_constant(name).is_some() {
+        return Err(ParseIssue::new(
+            diagnostics::DUPLICATE_DEFINITION,
+            Some(Token::m_label(name)),
+            format!("there's a constant with the name {name} already"),
+        ));
+    }
+    return Ok(());
+}
+pub fn parse_variable_decl(
+    tokens: &[Token],
+    tree: &ProgramTree,
+) -> Result<VariableDeclaration, ParseIssue> {
+    if let Some(name) = tokens.first().and_then(|e| e.to_label()) {
+        check_not_constant(&name, tree)?;
+    }
+    if match_exact_cond(
+        &[
+            (TokenReq::Label, IndexReq::Next),
+            (TokenReq::m_symbol(Colon), IndexReq::Next),
+        ],
+        tokens,
+    ) {
+        return parse_typed_variable_decl(tokens, tree);
+    }
     match_exact(
         &[
             (TokenReq::Label, IndexReq::Next),
             (TokenReq::m_symbol(Equal), IndexReq::Next),
-            (TokenReq::Number, IndexReq::Next),
+            (TokenReq::Any, IndexReq::Next),
             (TokenReq::None, IndexReq::Next),
         ],
         tokens,
     )?;
 
     let name = String::try_from(tokens[0].clone()).unwrap();
-    let def_value = i32::try_from(tokens[2].clone()).unwrap();
+    let def_value = parse_constant_value(&tokens[2], tree)?;
 
     return Ok(VariableDeclaration::new(name, def_value));
 }
+///"struct point x y"
+pub fn parse_struct_decl(tokens: &[Token], structs: &[Struct]) -> Result<Struct, ParseIssue> {
+    match_exact(
+        &[
+            (TokenReq::m_label("struct"), IndexReq::Next),
+            (TokenReq::Label, IndexReq::Next),
+            (TokenReq::Label, IndexReq::Between(2, tokens.len().max(3))),
+        ],
+        tokens,
+    )?;
+    let name = tokens[1].to_label().unwrap();
+    if name == "ptr" || name == "int" || structs.iter().any(|e| e.name == name) {
+        return Err(ParseIssue::new(
+            diagnostics::DUPLICATE_DEFINITION,
+            Some(tokens[1].clone()),
+            format!("there's a struct with the name {name} already"),
+        ));
+    }
+    let fields: Vec<String> = tokens[2..].iter().map(|e| e.to_label().unwrap()).collect();
+    for (i, field) in fields.iter().enumerate() {
+        if fields[..i].contains(field) {
+            return Err(ParseIssue::new(
+                diagnostics::DUPLICATE_DEFINITION,
+                Some(Token::m_label(field)),
+                format!("field {field} is defined twice in struct {name}"),
+            ));
+        }
+    }
+    return Ok(Struct::new(StructId(structs.len()), name, fields));
+}
+///"const SIZE = 16", value can be another constant
+///globals are the declarations parsed so far, they get into the tree after the whole section
+pub fn parse_named_constant(
+    tokens: &[Token],
+    tree: &ProgramTree,
+    globals: &[VariableDeclaration],
+) -> Result<NamedConstant, ParseIssue> {
+    match_exact(
+        &[
+            (TokenReq::m_label("const"), IndexReq::Next),
+            (TokenReq::Label, IndexReq::Next),
+            (TokenReq::m_symbol(Equal), IndexReq::Next),
+            (TokenReq::Any, IndexReq::Next),
+            (TokenReq::None, IndexReq::Next),
+        ],
+        tokens,
+    )?;
+    let name = tokens[1].to_label().unwrap();
+    check_not_constant(&name, tree)?;
+    if globals.iter().any(|e| e.name == name) {
+        return Err(ParseIssue::new(
+            diagnostics::DUPLICATE_DEFINITION,
+            Some(tokens[1].clone()),
+            format!("there's a global variable with the name {name} already"),
+        ));
+    }
+    return Ok(NamedConstant::new(
+        name,
+        parse_constant_value(&tokens[3], tree)?,
+    ));
+}
+///declaration that fails still defines its name with a placeholder,
+///so uses of the name don't give more errors
+fn push_declaration(
+    decl: Result<VariableDeclaration, ParseIssue>,
+    token_line: &TokenLine,
+    all: &mut Vec<VariableDeclaration>,
+    errors: &mut Vec<ParserError>,
+) {
+    let mut decl = match decl {
+        Ok(decl) => decl,
+        Err(err) => {
+            errors.push(ParserError::upgrade(err, token_line));
+            match token_line.elements.first() {
+                Some(Token::Label(name)) => VariableDeclaration::new(name.clone(), 0),
+                _ => return,
+            }
+        }
+    };
+    decl.doc = token_line.doc.clone();
+    decl.line = token_line.line_number;
+    all.push(decl);
+}
+///global section, can define structs and constants as well
+///lines that fail are put in errors and skipped
+pub fn parse_globals(
+    token_lines: &[TokenLine],
+    tree: &mut ProgramTree,
+    errors: &mut Vec<ParserError>,
+) -> Result<SuccessStep<Vec<VariableDeclaration>>, ParserError> {
+    let mut all = Vec::new();
+
+    let mut i = 0;
+    while i < token_lines.len() {
+        let token_line = &token_lines[i];
+
+        if match_exact_cond(
+            &[
+                (TokenReq::m_symbol(Symbol::Asterix), IndexReq::Beg(0)),
+                (TokenReq::None, IndexReq::Beg(1)),
+            ],
+            &token_line.elements,
+        ) {
+            return Ok(SuccessStep::new(all, i + 1));
+        }
+
+        let result = if token_line.elements[0].is_exact_label("struct") {
+            parse_struct_decl(&token_line.elements, &tree.structs).map(|e| tree.structs.push(e))
+        } else if token_line.elements[0].is_exact_label("const") {
+            parse_named_constant(&token_line.elements, tree, &all)
+                .map(|e| tree.named_constants.push(e))
+        } else {
+            let decl = parse_variable_decl(&token_line.elements, tree);
+            push_declaration(decl, token_line, &mut all, errors);
+            Ok(())
+        };
+        if let Err(err) = result {
+            errors.push(ParserError::upgrade(err, token_line));
+        }
+        i += 1;
+    }
+
+    return Err(ParserError::upgrade(
+        "Variable declaration not closed".to_owned(),
+        &token_lines.last().unwrap_or(&TokenLine::none()),
+    ));
+}
+///incorrect declarations are put in errors and skipped
 pub fn parse_variables(
     token_lines: &[TokenLine],
+    tree: &ProgramTree,
+    errors: &mut Vec<ParserError>,
 ) -> Result<SuccessStep<Vec<VariableDeclaration>>, ParserError> {
     let mut all = Vec::new();
 
@@ -652,9 +1724,8 @@ pub fn parse_variables(
             return Ok(SuccessStep::new(all, i + 1));
         }
 
-        let decl = parse_variable_decl(&token_lines[i].elements)
-            .map_err(|e| ParserError::upgrade(e, &token_line))?;
-        all.push(decl);
+        let decl = parse_variable_decl(&token_line.elements, tree);
+        push_declaration(decl, token_line, &mut all, errors);
         i += 1;
     }
 
@@ -675,14 +1746,70 @@ pub fn push_variables(
             name: var.name,
             default_value: var.default_value,
             id: VariableId::new(i, kind),
-            read_only: readonly,
+            read_only: readonly && !var.is_mut,
+            data_type: var.data_type,
+            doc: var.doc,
+            line: var.line,
         });
         i += 1;
     }
 }
 
+///"a b p: ptr<point>" or "a, b, p: ptr<point>", only pointers to structs can be passed
+///"mut a" allows assigning to a
+fn parse_fnc_arguments(
+    tokens: &[Token],
+    tree: &ProgramTree,
+) -> Result<Vec<VariableDeclaration>, ParseIssue> {
+    let mut tokens = tokens;
+    let mut args = Vec::new();
+    let with_commas = tokens.contains(&Token::Symbol(Comma));
+    while !tokens.is_empty() {
+        if with_commas && !args.is_empty() {
+            if tokens[0] != Token::Symbol(Comma) {
+                return Err(mixed_separators_issue(&tokens[0]));
+            }
+            tokens = &tokens[1..];
+            if tokens.len() == 0 {
+                return Err("Expected argument name after comma".into());
+            }
+        }
+        let is_mut = tokens[0] == Token::Label("mut".to_owned())
+            && matches!(tokens.get(1), Some(Token::Label(_)));
+        if is_mut {
+            tokens = &tokens[1..];
+        }
+        let name = tokens[0]
+            .to_label()
+            .ok_or_else(|| format!("Expected argument name, found {:?}", tokens[0]))?;
+        check_not_constant(&name, tree)?;
+        let mut decl = VariableDeclaration::new(name, -21);
+        decl.is_mut = is_mut;
+        tokens = &tokens[1..];
+        if tokens.first() == Some(&Token::Symbol(Colon)) {
+            let type_len = if tokens.get(2) == Some(&Token::Symbol(AngleOpen)) {
+                5
+            } else {
+                2
+            };
+            decl.data_type = parse_data_type(&tokens[1..type_len.min(tokens.len())], tree)?;
+            if let DataType::Struct(_) = decl.data_type {
+                return Err(format!(
+                    "argument {} can't be a struct, pass pointer to it instead",
+                    decl.name
+                )
+                .into());
+            }
+            tokens = &tokens[type_len.min(tokens.len())..];
+        }
+        args.push(decl);
+    }
+    return Ok(args);
+}
 pub fn parse_fnc_declaration<'a>(
     lines: &'a [TokenLine],
+    tree: &ProgramTree,
+    errors: &mut Vec<ParserError>,
 ) -> SingleStep<FunctionDeclaration, ParserError> {
     let first = &lines[0];
 
@@ -696,7 +1823,6 @@ pub fn parse_fnc_declaration<'a>(
                 IndexReq::Next,
             ),
             (TokenReq::Label, IndexReq::Next),
-            (TokenReq::Label, IndexReq::Between(2, first.elements.len())),
         ],
         &first,
     )?;
@@ -704,47 +1830,149 @@ pub fn parse_fnc_declaration<'a>(
     let is_stack = first.elements[0].to_label().unwrap() == "stack_function";
     let fnc_name = String::try_from(first.elements[1].clone()).unwrap();
 
-    let args: Vec<String> = first.elements[2..]
-        .iter()
-        .map(|e| e.to_label().unwrap())
-        .collect();
+    let args = parse_fnc_arguments(&first.elements[2..], tree)
+        .map_err(|e| ParserError::upgrade(e, first))?;
 
     let mut i = 1;
-    let locals = parse_variables(&lines[1..])?.apply(&mut i);
+    let locals = parse_variables(&lines[1..], tree, errors)?.apply(&mut i);
 
-    return SingleStepH::deliver(
-        FunctionDeclaration::new(fnc_name, args, locals, is_stack),
-        i,
-    );
+    if is_stack {
+        //stack functions save only one word per local
+        if let Some(el) = locals
+            .iter()
+            .find(|e| matches!(e.data_type, DataType::Struct(_)))
+        {
+            return Err(ParserError::upgrade(
+                format!(
+                    "stack function can't have struct local {}, use pointer",
+                    el.name
+                ),
+                first,
+            ));
+        }
+    }
+
+    let mut decl = FunctionDeclaration::new(fnc_name, args, locals, is_stack);
+    decl.doc = first.doc.clone();
+    decl.line = first.line_number;
+    for arg in decl.arguments.iter_mut() {
+        arg.line = first.line_number;
+    }
+    return SingleStepH::deliver(decl, i);
+}
+
+///every flag used in a function has to be defined there exactly once,
+///undefined flags are reported once at their first use
+fn check_flags(context: &BuildingContext) -> Vec<ParserError> {
+    let fnc_name = &context.cur_function.name;
+    let mut errors = Vec::new();
+    let mut defined = vec![false; context.flags.len()];
+    let name = |mark: &FlagMark| Some(Token::m_label(&context.flags[mark.raw]));
+    for mark in context.flag_marks.iter().filter(|e| e.is_definition) {
+        if defined[mark.raw] {
+            let issue = ParseIssue::new(
+                diagnostics::DUPLICATE_FLAG,
+                name(mark),
+                format!(
+                    "flag {} is defined twice in function {fnc_name}",
+                    context.flags[mark.raw]
+                ),
+            );
+            errors.push(ParserError::upgrade(issue, &mark.line));
+        }
+        defined[mark.raw] = true;
+    }
+    let mut reported = vec![false; context.flags.len()];
+    for mark in &context.flag_marks {
+        if defined[mark.raw] || reported[mark.raw] {
+            continue;
+        }
+        reported[mark.raw] = true;
+        let issue = ParseIssue::new(
+            diagnostics::UNDEFINED_FLAG,
+            name(mark),
+            format!(
+                "flag {} is not defined in function {fnc_name}",
+                context.flags[mark.raw]
+            ),
+        );
+        errors.push(ParserError::upgrade(issue, &mark.line));
+    }
+    return errors;
+}
+fn push_hidden_global(tree: &mut ProgramTree, name: &str) {
+    if tree.find_global_var_with_name(name).is_err() {
+        push_variables(
+            vec![VariableDeclaration::new(name.to_owned(), 0)],
+            &mut tree.globals,
+            VariableType::Global,
+            false,
+        );
+    }
 }
 
-pub fn push_next_function(tokens: &[TokenLine], tree: &mut ProgramTree) -> Step<(), ParserError> {
+///errors of single statements are put in errors, the function is still pushed
+pub fn push_next_function(
+    tokens: &[TokenLine],
+    tree: &mut ProgramTree,
+    errors: &mut Vec<ParserError>,
+) -> Step<(), ParserError> {
     if tokens.len() == 0 {
         return StepH::end();
     }
 
     let mut i = 0;
-    let decl = parse_fnc_declaration(tokens)?.apply(&mut i);
+    let decl = parse_fnc_declaration(tokens, tree, errors)?.apply(&mut i);
 
     if tree.find_fnc_with_name(&decl.name).is_some() {
-        return Err(ParserError::upgrade(
+        let issue = ParseIssue::new(
+            diagnostics::DUPLICATE_DEFINITION,
+            Some(Token::m_label(&decl.name)),
             "there's a function with the same name already".to_owned(),
-            &tokens[0],
-        ));
+        );
+        return Err(ParserError::upgrade(issue, &tokens[0]));
     }
 
     let id = tree.push_fnc(decl).id;
 
-    let mut context = BuildingContext::new(tree.get_fnc(id), &tree, None, HashSet::new());
+    let mut context = BuildingContext::new(
+        tree.get_fnc(id),
+        tree,
+        None,
+        FunctionUses::default(),
+        TokenLine::none(),
+    );
 
-    let block = parse_internal(&tokens[i..], &mut context)?.apply(&mut i);
+    let block = parse_internal(&tokens[i..], &mut context);
+    errors.append(&mut context.errors);
+    let block = block?.apply(&mut i);
+    errors.append(&mut check_flags(&context));
 
-    for el in context.constants {
+    let flags = context.flags;
+    let uses = context.uses;
+    for el in uses.constants {
         tree.constants_used.insert(el);
     }
+    for el in uses.strings {
+        if tree.find_string_index(&el).is_none() {
+            tree.strings_used.push(el);
+        }
+    }
+    tree.addresses_used.extend(uses.addresses);
+    tree.features |= uses.features;
+
+    //nested operations keep right side in hidden globals, one per nesting level
+    for height in 1..=uses.operation_temps {
+        push_hidden_global(tree, &get_operation_temp_name(height));
+    }
+    if tree.features.contains(FeatureFlags::StructPointers) {
+        push_hidden_global(tree, "_field_ptr");
+        push_hidden_global(tree, "_field_value");
+    }
 
     let fnc = tree.get_fnc_mut(id);
     fnc.content = Some(block);
+    fnc.flags = flags;
 
     //TODO constant "finding" in general is terribly written
 
@@ -790,7 +2018,7 @@ fn check_specific_function_exists(
 
 fn check_features(tree: &ProgramTree) -> Result<(), String> {
     if tree.features.contains(FeatureFlags::StackFunctions) {
-        const ERR: &'static str = "Stack feature was enabled,\nbut at least one of the required function definitions were missing or incorrect:";
+        const ERR: &str = "Stack feature was enabled,\nbut at least one of the required function definitions were missing or incorrect:";
 
         let map_er = |e| format!("{ERR}\n{e}");
         check_specific_function_exists(tree, "push", 1, false).map_err(map_er)?;
@@ -800,12 +2028,28 @@ fn check_features(tree: &ProgramTree) -> Result<(), String> {
     return Ok(());
 }
 
-pub fn parse(tokens: &[TokenLine]) -> ParserResult<ProgramTree> {
+///index of the next line starting a function, tokens.len() if there's none
+fn find_next_function(tokens: &[TokenLine]) -> usize {
+    return tokens
+        .iter()
+        .skip(1)
+        .position(|e| {
+            matches!(e.elements.first(),
+                Some(Token::Label(label)) if label == "function" || label == "stack_function")
+        })
+        .map_or(tokens.len(), |e| e + 1);
+}
+
+///all errors found, sorted by line
+pub fn parse(tokens: &[TokenLine]) -> Result<ProgramTree, Vec<ParserError>> {
     let mut tokens = tokens;
     let mut i = 0;
-    let global_variables = parse_variables(tokens)?.apply_a(&mut tokens, &mut i);
-
     let mut tree = ProgramTree::default();
+    let mut errors = Vec::new();
+
+    let global_variables = parse_globals(tokens, &mut tree, &mut errors)
+        .map_err(|e| vec![e])?
+        .apply_a(&mut tokens, &mut i);
 
     push_variables(
         vec![
@@ -826,28 +2070,41 @@ pub fn parse(tokens: &[TokenLine]) -> ParserResult<ProgramTree> {
 
     let mut i = 0;
     loop {
-        let step = push_next_function(tokens, &mut tree)?;
-        if step.is_none() {
-            break;
+        match push_next_function(tokens, &mut tree, &mut errors) {
+            Ok(Some(step)) => {
+                step.apply_a(&mut tokens, &mut i);
+            }
+            Ok(None) => break,
+            Err(err) => {
+                //skip rest of the function
+                errors.push(err);
+                let skip = find_next_function(tokens);
+                push_slice(&mut tokens, skip, &mut i);
+            }
         }
-        step.unwrap().apply_a(&mut tokens, &mut i);
+    }
+    if !errors.is_empty() {
+        //checks of the whole program would only repeat them
+        errors.sort_by_key(|e| e.line);
+        return Err(errors);
     }
 
     let main_fnc = tree
         .find_fnc_with_name("main")
-        .ok_or_else(|| ParserError::new(0, "".to_owned(), "no main function".to_owned()))?;
+        .ok_or_else(|| ParserError::new(0, "".to_owned(), "no main function".to_owned()))
+        .map_err(|e| vec![e])?;
 
     if main_fnc.is_stack {
-        return Err(ParserError::general(
+        return Err(vec![ParserError::general(
             "main function cannot be stack function".to_owned(),
-        ));
+        )]);
     }
     if main_fnc.args != 0 {
-        return Err(ParserError::general(
+        return Err(vec![ParserError::general(
             "main function cannot take arguments".to_owned(),
-        ));
+        )]);
     }
 
-    check_features(&tree).map_err(|e| ParserError::general(e))?;
+    check_features(&tree).map_err(|e| vec![ParserError::general(e)])?;
     return Ok(tree);
 }
@@ -0,0 +1,130 @@
+use crate::{
+    compiler::{find_function_range, find_origin, get_real_fnc_name_text, get_var_text},
+    diagnostics::escape_json,
+    parser::{Variable, VariableType},
+    type_checker::get_type_name,
+    Output,
+};
+
+///words from..to (exclusive) compiled from one source line
+#[derive(Debug, Clone)]
+pub struct SourceRange {
+    pub from: usize,
+    pub to: usize,
+    pub file: usize, //indexed by SourceFiles::files
+    pub line: usize, //starts from 0
+    pub function: String,
+}
+
+///ranges that don't overlap, innermost statement wins, code added by the compiler has none
+pub fn get_ranges(output: &Output) -> Vec<SourceRange> {
+    let mut bounds: Vec<usize> = output.origins.iter().flat_map(|e| [e.from, e.to]).collect();
+    bounds.sort();
+    bounds.dedup();
+
+    let mut ranges: Vec<SourceRange> = Vec::new();
+    for pair in bounds.windows(2) {
+        let origin = find_origin(&output.origins, pair[0]);
+        if origin.is_none() {
+            continue;
+        }
+        let origin = origin.unwrap();
+        let line = output.sources.code.get_origin(origin.line);
+        let range = SourceRange {
+            from: pair[0],
+            to: pair[1],
+            file: line.file,
+            line: line.line,
+            function: output.tree.get_fnc(origin.fnc).name.clone(),
+        };
+        //adjacent words of the same line make one range
+        match ranges.last_mut() {
+            Some(last)
+                if last.to == range.from
+                    && last.file == range.file
+                    && last.line == range.line
+                    && last.function == range.function =>
+            {
+                last.to = range.to;
+            }
+            _ => ranges.push(range),
+        }
+    }
+    return ranges;
+}
+
+fn symbol_json(var: &Variable, output: &Output) -> String {
+    let tree = &output.tree;
+    let (scope, function) = match var.id.kind {
+        VariableType::Global => ("global", "null".to_owned()),
+        VariableType::Local(id) => (
+            "local",
+            format!("\"{}\"", escape_json(&tree.get_fnc(id).name)),
+        ),
+    };
+    return format!(
+        "{{\"label\":\"{}\",\"name\":\"{}\",\"scope\":\"{}\",\"function\":{},\"address\":{},\"type\":\"{}\"}}",
+        escape_json(&get_var_text(var, tree)),
+        escape_json(&var.name),
+        scope,
+        function,
+        output.address_map[&var.id],
+        escape_json(&get_type_name(var.data_type, tree))
+    );
+}
+
+///json with the files, address ranges of source lines, functions and variables.
+///Lines start from 1, address ranges end is exclusive
+pub fn to_json(output: &Output) -> String {
+    let tree = &output.tree;
+    let sources = &output.sources;
+    let files: Vec<_> = sources
+        .files
+        .iter()
+        .map(|e| format!("\"{}\"", escape_json(e)))
+        .collect();
+    let ranges: Vec<_> = get_ranges(output)
+        .iter()
+        .map(|e| {
+            format!(
+                "{{\"from\":{},\"to\":{},\"file\":{},\"line\":{},\"function\":\"{}\"}}",
+                e.from,
+                e.to,
+                e.file,
+                e.line + 1,
+                escape_json(&e.function)
+            )
+        })
+        .collect();
+    let functions: Vec<_> = tree
+        .functions
+        .iter()
+        .filter_map(|fnc| {
+            let (from, to) = find_function_range(&output.origins, fnc.id)?;
+            let line = sources.code.get_origin(fnc.line);
+            return Some(format!(
+                "{{\"name\":\"{}\",\"label\":\"{}\",\"from\":{},\"to\":{},\"file\":{},\"line\":{},\"stack\":{}}}",
+                escape_json(&fnc.name),
+                escape_json(&get_real_fnc_name_text(fnc)),
+                from,
+                to,
+                line.file,
+                line.line + 1,
+                fnc.is_stack
+            ));
+        })
+        .collect();
+    let symbols: Vec<_> = tree
+        .globals
+        .iter()
+        .chain(tree.functions.iter().flat_map(|e| e.locals.iter()))
+        .map(|e| symbol_json(e, output))
+        .collect();
+    return format!(
+        "{{\n\"version\":1,\n\"files\":[{}],\n\"ranges\":[\n{}\n],\n\"functions\":[\n{}\n],\n\"symbols\":[\n{}\n]\n}}",
+        files.join(","),
+        ranges.join(",\n"),
+        functions.join(",\n"),
+        symbols.join(",\n")
+    );
+}
@@ -0,0 +1,209 @@
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    assembler, compile_source,
+    emulator::{BufferedIo, Machine, StopReason, ValueMode},
+    Options, Source,
+};
+
+pub const DEFAULT_MAX_STEPS: usize = 1_000_000;
+const SHOWN_VALUES: usize = 32; //of output of a program that didn't stop
+
+///one run of a program
+#[derive(Debug, Default, Clone)]
+pub struct TestCase {
+    pub input: Vec<u16>,
+    pub expected: Vec<u16>,
+}
+#[derive(Debug, Clone, Default)]
+pub struct TestFile {
+    pub path: PathBuf,
+    pub cases: Vec<TestCase>,
+    pub libs: Option<Vec<PathBuf>>, //"libs:" line, used instead of the configured ones
+}
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    Failed(Vec<u16>),  //what the program output
+    Timeout(Vec<u16>), //output until the step limit
+    InputEnded(Vec<u16>),
+}
+
+///"input:" and "expect:" lines, an input after an expect starts the next case.
+///"libs:" lists libs relative to the test file, can be empty
+pub fn parse_test<'a>(
+    path: &Path,
+    lines: impl Iterator<Item = &'a str>,
+    mode: ValueMode,
+) -> Result<TestFile, String> {
+    let mut cases: Vec<TestCase> = Vec::new();
+    let mut libs = None;
+    let mut expect_seen = true;
+    for line in lines {
+        let line = line.trim();
+        if let Some(names) = line.strip_prefix("libs:") {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            libs = Some(names.split_whitespace().map(|e| dir.join(e)).collect());
+            continue;
+        }
+        let (is_input, values) = match (line.strip_prefix("input:"), line.strip_prefix("expect:")) {
+            (Some(values), _) => (true, values),
+            (_, Some(values)) => (false, values),
+            _ => continue,
+        };
+        let values = parse_values(values.trim(), mode)?;
+        if (is_input && expect_seen) || cases.is_empty() {
+            cases.push(TestCase::default());
+        }
+        expect_seen = !is_input;
+        let case = cases.last_mut().unwrap();
+        match is_input {
+            true => case.input.extend(values),
+            false => case.expected.extend(values),
+        }
+    }
+    return Ok(TestFile {
+        path: path.to_owned(),
+        cases,
+        libs,
+    });
+}
+fn parse_values(text: &str, mode: ValueMode) -> Result<Vec<u16>, String> {
+    if mode == ValueMode::Ascii {
+        return Ok(text.chars().map(|e| e as u16).collect());
+    }
+    return text
+        .split_whitespace()
+        .map(|e| mode.parse(e).ok_or_else(|| format!("{e} is not a number")))
+        .collect();
+}
+pub fn format_values(values: &[u16], mode: ValueMode) -> String {
+    let values = values.iter().map(|e| mode.format(*e));
+    if mode == ValueMode::Ascii {
+        return format!("{:?}", values.collect::<String>());
+    }
+    return values.collect::<Vec<_>>().join(" ");
+}
+
+///test of the sidecar .io file, or of "// input:" and "// expect:" comments of the source
+pub fn read_test(path: &Path, mode: ValueMode) -> Result<TestFile, String> {
+    let sidecar = path.with_extension("io");
+    if sidecar.is_file() {
+        let text = fs::read_to_string(&sidecar).map_err(|e| e.to_string())?;
+        return parse_test(path, text.lines(), mode);
+    }
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let comments = text
+        .lines()
+        .filter_map(|e| e.trim_start().strip_prefix("//"));
+    return parse_test(path, comments, mode);
+}
+///every .crmarie file under path (or path itself) that has test cases, sorted by path
+pub fn discover(path: &Path, mode: ValueMode) -> Result<Vec<TestFile>, String> {
+    let mut paths = Vec::new();
+    collect_sources(path, &mut paths).map_err(|e| format!("{}: {e}", path.display()))?;
+    paths.sort();
+    let mut files = Vec::new();
+    for path in paths {
+        let file = read_test(&path, mode).map_err(|e| format!("{}: {e}", path.display()))?;
+        if !file.cases.is_empty() {
+            files.push(file);
+        }
+    }
+    return Ok(files);
+}
+///every .crmarie file under path, or path itself if it's a file
+pub fn collect_sources(path: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_file() {
+        paths.push(path.to_owned());
+        return Ok(());
+    }
+    for entry in fs::read_dir(path)? {
+        let entry = entry?.path();
+        if entry.is_dir() {
+            collect_sources(&entry, paths)?;
+        } else if entry.extension().is_some_and(|e| e == "crmarie") {
+            paths.push(entry);
+        }
+    }
+    return Ok(());
+}
+
+pub fn run_case(program: &[u16], case: &TestCase, max_steps: usize) -> Outcome {
+    let mut machine = Machine::new(program);
+    let mut io = BufferedIo::new(VecDeque::from(case.input.clone()));
+    return match machine.run(&mut io, Some(max_steps)) {
+        StopReason::Halt if io.output == case.expected => Outcome::Passed,
+        StopReason::Halt => Outcome::Failed(io.output),
+        StopReason::StepLimit => Outcome::Timeout(io.output),
+        StopReason::InputEnded => Outcome::InputEnded(io.output),
+    };
+}
+
+///compiles file with libs, runs every case and describes the problems, empty if all passed
+pub fn run_file(
+    file: &TestFile,
+    libs: &[Source],
+    mode: ValueMode,
+    max_steps: usize,
+) -> Result<Vec<String>, String> {
+    let read = |path: &Path| -> Result<Source, String> {
+        let code = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        return Ok(Source::new(path.display().to_string(), code));
+    };
+    let mut sources = vec![read(&file.path)?];
+    match &file.libs {
+        Some(own_libs) => {
+            for lib in own_libs {
+                sources.push(read(lib)?);
+            }
+        }
+        None => sources.extend(libs.iter().cloned()),
+    }
+    let output = compile_source(&sources, &Options::default()).map_err(|errors| {
+        let first = &errors.list[0];
+        let origin = errors.sources.code.get_origin(first.line);
+        format!(
+            "doesn't compile, {}:{}: {}",
+            errors.sources.files[origin.file],
+            origin.line + 1,
+            first.message
+        )
+    })?;
+    let assembled = assembler::assemble(&output.code).map_err(|e| e.content)?;
+
+    let mut problems = Vec::new();
+    for (i, case) in file.cases.iter().enumerate() {
+        let shown = |values: &[u16]| {
+            if values.len() > SHOWN_VALUES {
+                return format_values(&values[..SHOWN_VALUES], mode) + " ...";
+            }
+            return format_values(values, mode);
+        };
+        let problem = match run_case(&assembled.memory, case, max_steps) {
+            Outcome::Passed => continue,
+            Outcome::Failed(output) => format!(
+                "case {i}: wrong output\n    input:    {}\n    expected: {}\n    got:      {}",
+                format_values(&case.input, mode),
+                format_values(&case.expected, mode),
+                format_values(&output, mode)
+            ),
+            Outcome::Timeout(output) => format!(
+                "case {i}: timeout after {max_steps} steps\n    input:    {}\n    output:   {}",
+                format_values(&case.input, mode),
+                shown(&output)
+            ),
+            Outcome::InputEnded(output) => format!(
+                "case {i}: program asked for more input\n    input:    {}\n    output:   {}",
+                format_values(&case.input, mode),
+                shown(&output)
+            ),
+        };
+        problems.push(problem);
+    }
+    return Ok(problems);
+}
@@ -21,7 +21,6 @@ pub enum TokenReq {
     Literal(Token),
     Either(Token, Token),
     Label,
-    Number,
     Any,
     Inline,
     None,
@@ -49,7 +48,6 @@ impl Display for TokenReq {
         let t = match self {
             TokenReq::Literal(v) => format!("{:?}", v),
             TokenReq::Label => format!("any label"),
-            TokenReq::Number => format!("any number"),
             TokenReq::Any => format!("anything"),
             TokenReq::None => format!("nothing"),
             TokenReq::Inline => format!("inline assembly"),
@@ -64,7 +62,6 @@ impl TokenReq {
         return match self {
             TokenReq::Literal(v) => v == t,
             TokenReq::Label => matches!(t, Token::Label(_)),
-            TokenReq::Number => matches!(t, Token::Number(_)),
             TokenReq::Any => true,
             TokenReq::None => true,
             TokenReq::Inline => matches!(t, Token::Inline(_)),
@@ -107,9 +104,6 @@ fn pattern_to_readable(pattern: &[(TokenReq, IndexReq)]) -> String {
 pub fn match_exact_cond(pattern: &[(TokenReq, IndexReq)], tokens: &[Token]) -> bool {
     return match_exact(pattern, tokens).is_ok();
 }
-pub fn match_exact_ok(pattern: &[(TokenReq, IndexReq)], tokens: &[Token]) -> Option<()> {
-    return match_exact(pattern, tokens).ok();
-}
 pub fn match_exact(pattern: &[(TokenReq, IndexReq)], tokens: &[Token]) -> Result<(), String> {
     let mut next = 0;
     io::stdout().flush().unwrap();
@@ -0,0 +1,223 @@
+use crate::{
+    diagnostics,
+    lexer::{StrLiteral, Token, TokenLine},
+    parser::{
+        ArgumentCallArg, Block, DataType, FunctionCall, ParseIssue, ParserError, Place,
+        ProgramTree, Statement, VariableId,
+    },
+};
+
+///"int", "ptr<point>", ...
+pub fn get_type_name(t: DataType, tree: &ProgramTree) -> String {
+    return match t {
+        DataType::Word => "word".to_owned(),
+        DataType::Int => "int".to_owned(),
+        DataType::Ptr => "ptr".to_owned(),
+        DataType::Struct(id) => tree.get_struct(id).name.clone(),
+        DataType::StructPtr(id) => format!("ptr<{}>", tree.get_struct(id).name),
+    };
+}
+///type of the value arg evaluates to, word if it can't be known
+fn get_arg_type(arg: &ArgumentCallArg, tree: &ProgramTree) -> DataType {
+    return match arg {
+        ArgumentCallArg::Reference(id) => tree.get_var(*id).data_type,
+        ArgumentCallArg::GetAddress(id) => match tree.get_var(*id).data_type {
+            DataType::Struct(s) => DataType::StructPtr(s),
+            _ => DataType::Ptr,
+        },
+        ArgumentCallArg::Flag(_) | ArgumentCallArg::Str(_) => DataType::Ptr,
+        ArgumentCallArg::Operation(_) => DataType::Int,
+        ArgumentCallArg::Literal(_)
+        | ArgumentCallArg::Deref(_)
+        | ArgumentCallArg::Field(..)
+        | ArgumentCallArg::PtrField(..) => DataType::Word,
+    };
+}
+///token of the value in the source, errors about the value point at it
+fn get_arg_token(arg: &ArgumentCallArg, tree: &ProgramTree) -> Option<Token> {
+    return match arg {
+        ArgumentCallArg::Reference(id)
+        | ArgumentCallArg::GetAddress(id)
+        | ArgumentCallArg::Deref(id)
+        | ArgumentCallArg::Field(id, _)
+        | ArgumentCallArg::PtrField(id, _) => Some(Token::Label(tree.get_var(*id).name.clone())),
+        ArgumentCallArg::Flag(id) => Some(Token::Label(tree.get_flag_name(*id).to_owned())),
+        ArgumentCallArg::Str(content) => Some(Token::Str(StrLiteral {
+            content: content.clone(),
+            closed: true,
+        })),
+        ArgumentCallArg::Literal(value) => Some(Token::Number(*value)),
+        ArgumentCallArg::Operation(_) => None,
+    };
+}
+fn get_place_type(place: &Place, tree: &ProgramTree) -> DataType {
+    return match place {
+        Place::Variable(id) => tree.get_var(*id).data_type,
+        Place::Field(..) | Place::PtrField(..) => DataType::Word,
+    };
+}
+///can value of type from be stored in place of type to
+fn is_assignable(to: DataType, from: DataType) -> bool {
+    return match (to, from) {
+        (DataType::Word, _) | (_, DataType::Word) => true,
+        (DataType::Int, DataType::Int) => true,
+        (DataType::Ptr, DataType::Ptr | DataType::StructPtr(_)) => true,
+        (DataType::StructPtr(_), DataType::Ptr) => true,
+        (DataType::StructPtr(a), DataType::StructPtr(b)) => a == b,
+        _ => false,
+    };
+}
+fn check_deref(id: VariableId, tree: &ProgramTree) -> Result<(), ParseIssue> {
+    let var = tree.get_var(id);
+    let message = match var.data_type {
+        DataType::Int => format!("{} is int, it can't be dereferenced", var.name),
+        DataType::Struct(_) => format!(
+            "{} is a struct, it can't be dereferenced, use its fields",
+            var.name
+        ),
+        _ => return Ok(()),
+    };
+    let subject = Some(Token::Label(var.name.clone()));
+    return Err(ParseIssue::new(
+        diagnostics::INVALID_DEREFERENCE,
+        subject,
+        message,
+    ));
+}
+fn check_arg(arg: &ArgumentCallArg, tree: &ProgramTree) -> Result<(), ParseIssue> {
+    return match arg {
+        ArgumentCallArg::Deref(id) => check_deref(*id, tree),
+        ArgumentCallArg::Operation(op) => {
+            check_arg(&op.a, tree)?;
+            check_arg(&op.b, tree)?;
+            for value in [&op.a, &op.b] {
+                let t = get_arg_type(value, tree);
+                if !is_assignable(DataType::Int, t) {
+                    return Err(ParseIssue::new(
+                        diagnostics::OPERAND_TYPE,
+                        get_arg_token(value, tree),
+                        format!("operation expects int, found {}", get_type_name(t, tree)),
+                    ));
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+}
+fn check_assignable(
+    to: DataType,
+    right: &ArgumentCallArg,
+    target: &str,
+    tree: &ProgramTree,
+) -> Result<(), ParseIssue> {
+    check_arg(right, tree)?;
+    let from = get_arg_type(right, tree);
+    if !is_assignable(to, from) {
+        return Err(ParseIssue::new(
+            diagnostics::TYPE_MISMATCH,
+            get_arg_token(right, tree),
+            format!(
+                "{} is {}, it can't be given {}",
+                target,
+                get_type_name(to, tree),
+                get_type_name(from, tree)
+            ),
+        ));
+    }
+    return Ok(());
+}
+fn check_call(call: &FunctionCall, tree: &ProgramTree) -> Result<(), ParseIssue> {
+    let fnc = tree.get_fnc(call.fnc_id);
+    for (param, arg) in fnc.locals[..fnc.args].iter().zip(&call.arguments) {
+        let target = format!("argument {} of {}", param.name, fnc.name);
+        check_assignable(param.data_type, arg, &target, tree)?;
+    }
+    return Ok(());
+}
+fn check_statement(statement: &Statement, tree: &ProgramTree) -> Result<(), ParseIssue> {
+    match statement {
+        Statement::Assignment(assignment) => {
+            let target = match assignment.left {
+                Place::Variable(id) => tree.get_var(id).name.clone(),
+                _ => "field".to_owned(),
+            };
+            let to = get_place_type(&assignment.left, tree);
+            check_assignable(to, &assignment.right, &target, tree)?;
+        }
+        Statement::FunctionCall(call) => check_call(call, tree)?,
+        Statement::If(if_statement) => {
+            check_arg(&if_statement.a, tree)?;
+            check_arg(&if_statement.b, tree)?;
+        }
+        Statement::Switch(switch) => check_arg(&switch.value, tree)?,
+        Statement::Ret(ret) => {
+            if let Some(value) = &ret.ret_val {
+                check_arg(value, tree)?;
+            }
+        }
+        Statement::Inline(_) | Statement::Flag(_) | Statement::Goto(_) => {}
+    }
+    return Ok(());
+}
+///error at the subject of the issue in the tokens of the line
+fn locate_issue(issue: ParseIssue, line: usize, lines: &[TokenLine]) -> ParserError {
+    let mut same_line = lines.iter().filter(|e| e.line_number == line);
+    let subject = issue.subject.as_ref();
+    let with_subject = same_line
+        .clone()
+        .find(|e| subject.is_some_and(|s| e.elements.contains(s)));
+    return match with_subject.or_else(|| same_line.next()) {
+        Some(token_line) => ParserError::upgrade(issue, token_line),
+        None => {
+            let mut err = ParserError::new(line, String::new(), issue.message);
+            err.code = Some(issue.code);
+            err
+        }
+    };
+}
+fn check_block(
+    block: &Block,
+    tree: &ProgramTree,
+    lines: &[TokenLine],
+    errors: &mut Vec<ParserError>,
+) {
+    for line_statement in block {
+        if let Err(issue) = check_statement(&line_statement.statement, tree) {
+            errors.push(locate_issue(issue, line_statement.line, lines));
+        }
+
+        match &line_statement.statement {
+            Statement::If(if_statement) => {
+                check_block(&if_statement.if_true, tree, lines, errors);
+                if let Some(if_false) = &if_statement.if_false {
+                    check_block(if_false, tree, lines, errors);
+                }
+            }
+            Statement::Switch(switch) => {
+                for (_, case) in &switch.cases {
+                    check_block(case, tree, lines, errors);
+                }
+                if let Some(default) = &switch.default {
+                    check_block(default, tree, lines, errors);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+///checks that annotated variables get values of matching type,
+///untyped variables are words and match anything, lines are the tokens tree was parsed from
+pub fn check(tree: &ProgramTree, lines: &[TokenLine]) -> Result<(), Vec<ParserError>> {
+    let mut errors = Vec::new();
+    for fnc in &tree.functions {
+        if let Some(content) = &fnc.content {
+            check_block(content, tree, lines, &mut errors);
+        }
+    }
+    if !errors.is_empty() {
+        errors.sort_by_key(|e| e.line);
+        return Err(errors);
+    }
+    return Ok(());
+}
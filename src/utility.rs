@@ -28,25 +28,6 @@ impl<'a> StringExtension<'a> for &'a str {
     }
 }
 
-pub fn build_step_simple<T, TN, F, TR>(a: &[T], f: F) -> Result<Vec<TN>, TR>
-where
-    F: Fn(&[T]) -> Result<Option<(TN, &[T])>, TR>,
-{
-    let mut a = a;
-    let mut vec = Vec::new();
-    loop {
-        let result = f(a)?;
-        match result {
-            None => break,
-            Some(v) => {
-                a = v.1;
-                vec.push(v.0);
-            }
-        }
-    }
-    return Ok(vec);
-}
-
 pub fn build_step<T, F, TR>(a: &[T], f: F) -> Result<Vec<T>, TR>
 where
     F: Fn(&[T]) -> Step<T, TR>,
@@ -74,6 +55,10 @@ where
     pub line: usize, // starts from 0
     pub related_text: String,
     pub content: T,
+    #[new(default)]
+    pub code: Option<&'static str>, //kind of the error, see diagnostics
+    #[new(default)]
+    pub columns: Option<(usize, usize)>, //part of the line it's about, from 0, end exclusive
 }
 
 #[derive(Debug, derive_new::new)]
@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+
+use crate::parser::{
+    ArgumentCallArg, Block, FlagId, FunctionId, InlinePart, Place, ProgramTree, Statement,
+    VariableId,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WarningKind {
+    UnusedVariable,
+    UnusedFunction,
+    Unreachable,
+    UnusedFlag,
+}
+impl WarningKind {
+    ///stable name used in machine readable output
+    pub fn code(self) -> &'static str {
+        return match self {
+            WarningKind::UnusedVariable => "unused-variable",
+            WarningKind::UnusedFunction => "unused-function",
+            WarningKind::Unreachable => "unreachable-code",
+            WarningKind::UnusedFlag => "unused-flag",
+        };
+    }
+}
+#[derive(Debug, derive_new::new)]
+pub struct Warning {
+    pub line: usize, // starts from 0
+    pub kind: WarningKind,
+    pub content: String,
+}
+
+///functions compiler calls on its own for stack functions
+const STACK_HELPERS: [&str; 3] = ["push", "pop", "stack_return"];
+
+///what the program reads, calls and jumps to
+#[derive(Default)]
+struct Usage {
+    read: HashSet<VariableId>,
+    called: HashSet<FunctionId>,
+    jumped: HashSet<FlagId>,
+    flag_lines: Vec<(FlagId, usize)>, //definitions
+}
+
+fn mark_arg(arg: &ArgumentCallArg, usage: &mut Usage) {
+    match arg {
+        ArgumentCallArg::Reference(id)
+        | ArgumentCallArg::Deref(id)
+        | ArgumentCallArg::GetAddress(id)
+        | ArgumentCallArg::Field(id, _)
+        | ArgumentCallArg::PtrField(id, _) => {
+            usage.read.insert(*id);
+        }
+        ArgumentCallArg::Flag(id) => {
+            usage.jumped.insert(*id);
+        }
+        ArgumentCallArg::Operation(op) => {
+            mark_arg(&op.a, usage);
+            mark_arg(&op.b, usage);
+        }
+        ArgumentCallArg::Literal(_) | ArgumentCallArg::Str(_) => {}
+    }
+}
+///p->x = ... reads p, x = ... doesn't read x
+fn mark_place(place: &Place, usage: &mut Usage) {
+    if let Place::PtrField(id, _) = place {
+        usage.read.insert(*id);
+    }
+}
+fn mark_statement(statement: &Statement, line: usize, usage: &mut Usage, out: &mut Vec<Warning>) {
+    match statement {
+        Statement::Inline(parts) => {
+            for part in parts {
+                match part {
+                    InlinePart::Variable(id) | InlinePart::Field(id, _) => {
+                        usage.read.insert(*id);
+                    }
+                    InlinePart::Flag(id) => {
+                        usage.jumped.insert(*id);
+                    }
+                    InlinePart::Text(_) | InlinePart::Constant(_) => {}
+                }
+            }
+        }
+        Statement::If(if_statement) => {
+            mark_arg(&if_statement.a, usage);
+            mark_arg(&if_statement.b, usage);
+            mark_block(&if_statement.if_true, usage, out);
+            if let Some(if_false) = &if_statement.if_false {
+                mark_block(if_false, usage, out);
+            }
+        }
+        Statement::FunctionCall(call) => {
+            usage.called.insert(call.fnc_id);
+            for arg in &call.arguments {
+                mark_arg(arg, usage);
+            }
+            if let Some(place) = &call.assignment {
+                mark_place(place, usage);
+            }
+        }
+        Statement::Flag(flag) => usage.flag_lines.push((flag.id, line)),
+        Statement::Goto(id) => {
+            usage.jumped.insert(*id);
+        }
+        Statement::Switch(switch) => {
+            mark_arg(&switch.value, usage);
+            for (_, case) in &switch.cases {
+                mark_block(case, usage, out);
+            }
+            if let Some(default) = &switch.default {
+                mark_block(default, usage, out);
+            }
+        }
+        Statement::Ret(ret) => {
+            if let Some(value) = &ret.ret_val {
+                mark_arg(value, usage);
+            }
+        }
+        Statement::Assignment(assignment) => {
+            mark_place(&assignment.left, usage);
+            mark_arg(&assignment.right, usage);
+        }
+    }
+}
+fn mark_block(block: &Block, usage: &mut Usage, out: &mut Vec<Warning>) {
+    let mut after_ret = false;
+    for line_statement in block {
+        let statement = &line_statement.statement;
+        //flags and inline labels can still be jumped to
+        let can_be_target = matches!(statement, Statement::Flag(_) | Statement::Inline(_));
+        if after_ret && !can_be_target {
+            out.push(Warning::new(
+                line_statement.line,
+                WarningKind::Unreachable,
+                "unreachable code after .ret".to_owned(),
+            ));
+        }
+        after_ret = matches!(statement, Statement::Ret(_)) || (after_ret && !can_be_target);
+        mark_statement(statement, line_statement.line, usage, out);
+    }
+}
+///"_temp", "return" and the ones added by the compiler
+fn is_hidden(name: &str) -> bool {
+    return name.starts_with('_') || name == "return" || name == "return_saver";
+}
+
+///"// nowarn" comment turns off warnings for its line
+pub fn is_suppressed(line: &str) -> bool {
+    return line.split_once("//").is_some_and(|(_, comment)| {
+        comment
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|e| e == "nowarn")
+    });
+}
+
+///warnings sorted by line, caller decides which ones to show
+pub fn check(tree: &ProgramTree) -> Vec<Warning> {
+    let mut out = Vec::new();
+    let mut usage = Usage::default();
+    for fnc in &tree.functions {
+        if let Some(content) = &fnc.content {
+            mark_block(content, &mut usage, &mut out);
+        }
+    }
+
+    for var in &tree.globals {
+        if !is_hidden(&var.name) && !usage.read.contains(&var.id) {
+            out.push(Warning::new(
+                var.line,
+                WarningKind::UnusedVariable,
+                format!("global {} is never read", var.name),
+            ));
+        }
+    }
+    for fnc in tree.functions.iter().filter(|e| e.content.is_some()) {
+        for var in &fnc.locals[fnc.args..] {
+            if !usage.read.contains(&var.id) {
+                out.push(Warning::new(
+                    var.line,
+                    WarningKind::UnusedVariable,
+                    format!("local {} of function {} is never read", var.name, fnc.name),
+                ));
+            }
+        }
+        let is_entry = fnc.name == "main" || STACK_HELPERS.contains(&fnc.name.as_str());
+        if !is_entry && !usage.called.contains(&fnc.id) {
+            out.push(Warning::new(
+                fnc.line,
+                WarningKind::UnusedFunction,
+                format!("function {} is never called", fnc.name),
+            ));
+        }
+    }
+    for (id, line) in &usage.flag_lines {
+        if !usage.jumped.contains(id) {
+            out.push(Warning::new(
+                *line,
+                WarningKind::UnusedFlag,
+                format!("flag {} is never jumped to", tree.get_flag_name(*id)),
+            ));
+        }
+    }
+
+    out.sort_by_key(|e| e.line);
+    return out;
+}
@@ -0,0 +1,114 @@
+//!runs the crystal-marie binary, checks how arguments are read and what fmt writes
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+const UNFORMATTED: &str = "*\nfunction main\n*\n{\noutput(1)   \n\n\n}\n";
+const FORMATTED: &str = "*\nfunction main\n*\n{\n    output(1)\n\n}\n";
+
+fn crystal_marie(args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_crystal-marie"))
+        .args(args)
+        .output()
+        .unwrap();
+}
+fn stderr(output: &Output) -> String {
+    return String::from_utf8_lossy(&output.stderr).into_owned();
+}
+///file with the unformatted program, name is unique for each test
+fn temp_source(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!(
+        "crystal_marie_{}_{name}.crmarie",
+        std::process::id()
+    ));
+    fs::write(&path, UNFORMATTED).unwrap();
+    return path;
+}
+
+#[test]
+fn help_lists_commands_and_options() {
+    let output = crystal_marie(&["--help"]);
+    assert!(output.status.success());
+    let help = String::from_utf8_lossy(&output.stdout);
+    for expected in ["fmt", "debug", "--lib", "--write", "--diagnostics"] {
+        assert!(help.contains(expected), "{expected} missing in {help}");
+    }
+    assert_eq!(crystal_marie(&["-h"]).stdout, output.stdout);
+}
+
+#[test]
+fn wrong_arguments_are_reported() {
+    let cases = [
+        (
+            vec!["x.crmarie", "--nope"],
+            "Unknown argument kind : --nope",
+        ),
+        (
+            vec!["x.crmarie", "-s", "extra"],
+            "s argument takes no values",
+        ),
+        (
+            vec!["x.crmarie", "-o", "a", "-o", "b"],
+            "Specified twice: -o",
+        ),
+        (
+            vec!["x.crmarie", "--emit", "png"],
+            "emit argument takes one value",
+        ),
+        (
+            vec!["x.crmarie", "-e", "many"],
+            "max-errors expects a number",
+        ),
+    ];
+    for (args, message) in cases {
+        let output = crystal_marie(&args);
+        assert!(!output.status.success(), "{args:?}");
+        assert!(
+            stderr(&output).contains(message),
+            "{args:?}: {}",
+            stderr(&output)
+        );
+    }
+}
+
+#[test]
+fn fmt_prints_and_leaves_the_file_alone() {
+    let path = temp_source("print");
+    let output = crystal_marie(&["fmt", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), FORMATTED);
+    assert_eq!(fs::read_to_string(&path).unwrap(), UNFORMATTED);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn fmt_rewrites_the_file_with_write() {
+    for flag in ["-i", "--write"] {
+        let path = temp_source(&format!("write{flag}"));
+        let output = crystal_marie(&["fmt", path.to_str().unwrap(), flag]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert!(output.stdout.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), FORMATTED);
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn fmt_writes_other_file_with_output() {
+    let path = temp_source("output");
+    let formatted_path = path.with_extension("formatted");
+    let output = crystal_marie(&[
+        "fmt",
+        path.to_str().unwrap(),
+        "-o",
+        formatted_path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(&formatted_path).unwrap(), FORMATTED);
+    assert_eq!(fs::read_to_string(&path).unwrap(), UNFORMATTED);
+    fs::remove_file(path).unwrap();
+    fs::remove_file(formatted_path).unwrap();
+}
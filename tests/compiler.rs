@@ -0,0 +1,396 @@
+//!small programs compiled with std.crmarie, each test checks one feature of the language
+//!by running the program on the emulator or by the errors it gets
+
+use std::{collections::VecDeque, fs, path::Path};
+
+use crystal_marie::{
+    assembler, compile_source,
+    emulator::{BufferedIo, Machine, StopReason},
+    Diagnostics, Options, Output, Source,
+};
+
+fn std_lib() -> Source {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("std.crmarie");
+    return Source::new("std.crmarie".to_owned(), fs::read_to_string(path).unwrap());
+}
+fn compile(code: &str) -> Result<Output, Diagnostics> {
+    let sources = vec![
+        Source::new("test.crmarie".to_owned(), code.to_owned()),
+        std_lib(),
+    ];
+    return compile_source(&sources, &Options::default());
+}
+///runs the program until halt, returns what it output
+fn run(code: &str, input: &[i16]) -> Vec<i16> {
+    let output = compile(code).unwrap_or_else(|e| panic!("doesn't compile: {:?}", e.list));
+    let memory = assembler::assemble(&output.code).unwrap().memory;
+    let mut machine = Machine::new(&memory);
+    let mut io = BufferedIo::new(input.iter().map(|e| *e as u16).collect::<VecDeque<_>>());
+    assert_eq!(machine.run(&mut io, Some(100_000)), StopReason::Halt);
+    return io.output.iter().map(|e| *e as i16).collect();
+}
+fn errors_of(code: &str) -> Diagnostics {
+    return match compile(code) {
+        Ok(_) => panic!("compiled, errors were expected"),
+        Err(errors) => errors,
+    };
+}
+///line in the test file (from 1) and code of every error
+fn error_codes(code: &str) -> Vec<(usize, &'static str)> {
+    let errors = errors_of(code);
+    return errors
+        .list
+        .iter()
+        .map(|e| (errors.sources.get_origin(e.line).1 + 1, e.code))
+        .collect();
+}
+
+const DIVMOD: &str = "\
+*
+function main
+a=0
+b=0
+*
+{
+    a=input()
+    b=input()
+    output((a / b))
+    output((a % b))
+}
+";
+
+#[test]
+fn division_by_zero_gives_zero_and_remainder_gives_dividend() {
+    assert_eq!(run(DIVMOD, &[7, 0]), [0, 7]);
+    assert_eq!(run(DIVMOD, &[-7, 0]), [0, -7]);
+}
+
+#[test]
+fn division_rounds_towards_zero() {
+    assert_eq!(run(DIVMOD, &[7, 2]), [3, 1]);
+    assert_eq!(run(DIVMOD, &[-7, 2]), [-3, -1]);
+    assert_eq!(run(DIVMOD, &[7, -2]), [-3, 1]);
+    assert_eq!(run(DIVMOD, &[-7, -2]), [3, -1]);
+}
+
+#[test]
+fn operators_need_parentheses_only_between_spaced_arguments() {
+    let code = "\
+*
+function main
+a=6
+b=4
+x=0
+*
+{
+    x = add(a * b, 1)
+    output(x)
+    x = sub((a * b) 1)
+    output(x)
+    output((a * b / 3 % 5))
+}
+";
+    assert_eq!(run(code, &[]), [25, 23, 3]);
+    let code = "*\nfunction main\na=6\n*\n{\n    a = add(a / 2)\n}\n";
+    assert_eq!(error_codes(code), [(6, "missing-parentheses")]);
+}
+
+#[test]
+fn star_before_name_between_spaced_arguments_is_dereference() {
+    let code = "\
+v=7
+*
+function main
+x=5
+p=0
+*
+{
+    p := &v
+    x = add(x *p)
+    output(x)
+    x = add(x, *p)
+    output(x)
+}
+";
+    assert_eq!(run(code, &[]), [12, 19]);
+}
+
+#[test]
+fn struct_fields_through_variables_and_pointers() {
+    let code = "\
+struct point x y
+origin: point
+*
+function move p: ptr<point> dx
+*
+{
+    p->x = add(p->x dx)
+}
+function main
+a: point
+q: ptr<point> = 0
+*
+{
+    a.x := 3
+    a.y := 4
+    move(&a 10)
+    output(a.x)
+    output(a.y)
+    q := &origin
+    q->y := 9
+    output(origin.x)
+    output(origin.y)
+}
+";
+    assert_eq!(run(code, &[]), [13, 4, 0, 9]);
+    let code = "struct point x y\n*\nfunction main\na: point\n*\n{\n    a.z := 1\n}\n";
+    assert_eq!(error_codes(code), [(7, "undefined-field")]);
+}
+
+#[test]
+fn constants_are_replaced_with_their_values() {
+    let code = "\
+const SIZE = 16
+const LAST = SIZE
+total=SIZE
+*
+function main
+i=LAST
+*
+{
+    i = sub(i 6)
+    output(i)
+    output(total)
+    output((SIZE * 2))
+}
+";
+    assert_eq!(run(code, &[]), [10, 16, 32]);
+    let code = "const SIZE = 16\n*\nfunction main\n*\n{\n    SIZE := 3\n}\n";
+    assert_eq!(error_codes(code), [(6, "parse-error")]);
+}
+
+#[test]
+fn constant_and_global_with_the_same_name_are_duplicates() {
+    let body = "*\nfunction main\n*\n{\n    x := 3\n}\n";
+    let code = format!("x=0\nconst x = 5\n{body}");
+    assert_eq!(error_codes(&code), [(2, "duplicate-definition")]);
+    let code = format!("const x = 5\nx=0\n{body}");
+    assert_eq!(error_codes(&code)[0], (2, "duplicate-definition"));
+}
+
+///main outputs 10*case for every case and 0 for default
+fn switch_program(cases: &[i32]) -> String {
+    let arms: String = cases
+        .iter()
+        .map(|e| {
+            format!(
+                "    .case({e})\n    {{\n        output({})\n    }}\n",
+                e * 10
+            )
+        })
+        .collect();
+    return format!(
+        "*\nfunction main\nx=0\n*\n{{\n    x=input()\n    .switch(x)\n    {{\n{arms}    .default()\n    {{\n        output(0)\n    }}\n    }}\n}}\n"
+    );
+}
+fn has_jump_table(cases: &[i32]) -> bool {
+    let output = compile(&switch_program(cases)).unwrap_or_else(|e| panic!("{:?}", e.list));
+    return output.code.contains("table_");
+}
+
+#[test]
+fn switch_uses_jump_table_for_four_close_cases() {
+    assert!(!has_jump_table(&[1, 2, 3]));
+    assert!(has_jump_table(&[1, 2, 3, 4]));
+    //range of 8 values is at most twice the cases
+    assert!(has_jump_table(&[1, 3, 6, 8]));
+    assert!(!has_jump_table(&[1, 3, 6, 9]));
+    assert!(!has_jump_table(&[0, 1, 2, 32767]));
+}
+
+#[test]
+fn switch_arms_run_with_and_without_table() {
+    for cases in [[1, 2, 3, 4], [1, 3, 6, 9]] {
+        let code = switch_program(&cases);
+        for value in [0, 1, 3, 4, 5, 9, 10] {
+            let expected = if cases.contains(&value) {
+                value * 10
+            } else {
+                0
+            };
+            assert_eq!(
+                run(&code, &[value as i16]),
+                [expected as i16],
+                "cases {cases:?}, value {value}"
+            );
+        }
+    }
+}
+
+#[test]
+fn switch_case_has_to_fit_in_a_word() {
+    let code = switch_program(&[0, 1, 2, 3]).replace(".case(3)", ".case(2147483647)");
+    assert_eq!(error_codes(&code), [(21, "parse-error")]);
+}
+
+#[test]
+fn type_errors_point_at_the_value() {
+    let code = "\
+*
+function main
+n: int = 0
+p: ptr = 0
+w=0
+*
+{
+    n := p
+    w := *n
+    n := (p * 2)
+}
+";
+    let errors = errors_of(code);
+    let found: Vec<_> = errors.list.iter().map(|e| (e.code, e.columns)).collect();
+    assert_eq!(
+        found,
+        [
+            ("type-mismatch", Some((9, 10))),
+            ("invalid-dereference", Some((10, 11))),
+            ("operand-type", Some((10, 11)))
+        ]
+    );
+    assert_eq!(
+        error_codes(code),
+        [
+            (8, "type-mismatch"),
+            (9, "invalid-dereference"),
+            (10, "operand-type")
+        ]
+    );
+}
+
+#[test]
+fn unused_and_unreachable_code_gives_warnings() {
+    let code = "\
+unused=0
+read=1
+*
+function never
+*
+{
+}
+function main
+x=0
+*
+{
+    .flag(beg)
+    output(read)
+    .ret(0)
+    output(read)
+}
+";
+    let output = compile(code).unwrap_or_else(|e| panic!("{:?}", e.list));
+    let found: Vec<_> = output
+        .warnings
+        .iter()
+        .map(|e| (output.sources.get_origin(e.line).1 + 1, e.code))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (1, "unused-variable"),
+            (4, "unused-function"),
+            (9, "unused-variable"),
+            (12, "unused-flag"),
+            (15, "unreachable-code")
+        ]
+    );
+}
+
+#[test]
+fn nowarn_comment_turns_off_warnings_of_its_line() {
+    let code = "unused=0 // nowarn\n*\nfunction main\n*\n{\n}\n";
+    assert!(compile(code).unwrap().warnings.is_empty());
+    let options = Options {
+        warnings_as_errors: true,
+        ..Options::default()
+    };
+    let sources = [Source::new(
+        "test.crmarie".to_owned(),
+        "unused=0\n*\nfunction main\n*\n{\n}\n".to_owned(),
+    )];
+    let errors = compile_source(&sources, &options).unwrap_err();
+    assert_eq!(errors.list[0].code, "unused-variable");
+}
+
+#[test]
+fn every_error_of_the_file_is_reported() {
+    let code = "\
+*
+function first
+a=0
+b
+*
+{
+    a = add(a missing)
+}
+function main
+*
+{
+    first()
+    .goto(nowhere)
+    unknown_function()
+}
+";
+    assert_eq!(
+        error_codes(code),
+        [
+            (4, "parse-error"),
+            (7, "undefined-variable"),
+            (13, "undefined-flag"),
+            (14, "undefined-function")
+        ]
+    );
+}
+
+#[test]
+fn failed_declaration_still_defines_the_name() {
+    let code = "g=-1\n*\nfunction main\nb=-2\n*\n{\n    output(b)\n    output(g)\n}\n";
+    assert_eq!(error_codes(code), [(1, "parse-error"), (4, "parse-error")]);
+}
+
+#[test]
+fn json_diagnostic_points_at_the_token() {
+    let code = "*\nfunction main\nx=0\n*\n{\n    x = add(x missing)\n}\n";
+    let errors = errors_of(code);
+    assert_eq!(errors.list.len(), 1);
+    let json = errors.list[0].to_json(&errors.sources);
+    assert!(
+        json.starts_with(
+            "{\"file\":\"test.crmarie\",\"line\":6,\"column_start\":15,\"column_end\":22,\"severity\":\"error\",\"code\":\"undefined-variable\","
+        ),
+        "{json}"
+    );
+}
+
+#[test]
+fn block_comment_spans_lines_and_has_to_be_closed() {
+    let code = "*\nfunction main\n*\n{\n    /* output(1)\n    output(2) */ output(3)\n}\n";
+    assert_eq!(run(code, &[]), [3]);
+    let code = "x=0\n/* never closed\n*\nfunction main\n*\n{\n    output(x)\n}\n";
+    assert_eq!(error_codes(code), [(2, "unclosed-comment")]);
+}
+
+#[test]
+fn inline_assembly_is_not_searched_for_block_comments() {
+    let code = "\
+x=5
+*
+function main
+*
+{
+    %load {x} /* marie comment
+    %output
+}
+";
+    assert_eq!(run(code, &[]), [5]);
+}
@@ -0,0 +1,123 @@
+//!runs `crystal-marie debug` with commands on stdin and checks what it answers
+
+use std::{
+    env, fs,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+const PROGRAM: &str = "\
+total=0
+*
+function twice mut x
+*
+{
+    x = add(x x)
+    .ret(x)
+}
+function main
+a=3
+*
+{
+    a = twice(a)
+    total := a
+    output(total)
+}
+";
+
+///answers of the debugger, one per command, and its errors, file name replaced with "file"
+fn debug(name: &str, commands: &str) -> (Vec<String>, String) {
+    let path = env::temp_dir().join(format!(
+        "crystal_marie_debug_{}_{name}.crmarie",
+        std::process::id()
+    ));
+    fs::write(&path, PROGRAM).unwrap();
+    let std_lib = Path::new(env!("CARGO_MANIFEST_DIR")).join("std.crmarie");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crystal-marie"))
+        .args([
+            "debug",
+            path.to_str().unwrap(),
+            "-l",
+            std_lib.to_str().unwrap(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8_lossy(&output.stdout).replace(path.to_str().unwrap(), "file");
+    let answers = text
+        .split("(debug) ")
+        .map(|e| e.trim_end().to_owned())
+        .collect();
+    return (
+        answers,
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    );
+}
+
+#[test]
+fn breakpoint_stops_in_the_function_with_its_caller_below() {
+    let (answers, _) = debug(
+        "break",
+        "break twice\nbreakpoints\ncontinue\nbacktrace\nprint x\nquit\n",
+    );
+    //address of the function depends on the code before it
+    assert!(
+        answers[2].starts_with("0: function twice ("),
+        "{}",
+        answers[2]
+    );
+    assert_eq!(
+        answers,
+        [
+            "main at file:9  function main",
+            "breakpoint 0 at twice at file:6  x = add(x x)",
+            answers[2].as_str(),
+            "breakpoint 0, twice at file:6  x = add(x x)",
+            "#0 twice at file:6\n#1 main at file:13",
+            "x = 3 (0x0003)",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn finish_and_next_go_through_source_lines() {
+    let (answers, _) = debug(
+        "step",
+        "break twice\ncontinue\nfinish\nnext\nprint a\nwhere\ncontinue\nquit\n",
+    );
+    assert_eq!(
+        answers[3..],
+        [
+            "main at file:13  a = twice(a)",
+            "main at file:14  total := a",
+            "a = 6 (0x0006)",
+            "main at file:14  total := a",
+            "6\nprogram halted",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn wrong_commands_are_reported_and_debugging_goes_on() {
+    let (answers, errors) = debug("wrong", "print nothing\nbreak nowhere\nwhere\nquit\n");
+    assert_eq!(
+        errors.lines().filter(|e| e.starts_with("err: ")).count(),
+        2,
+        "{errors}"
+    );
+    assert_eq!(answers[answers.len() - 2], "main at file:9  function main");
+}
@@ -0,0 +1,146 @@
+//!examples compiled with std.crmarie are compared with the marie code in their output folders,
+//!`UPDATE_SNAPSHOTS=1 cargo test` rewrites the outputs instead.
+//!Outputs change with every change of code generation, so examples are also run on the emulator
+//!against their .io files, that checks the new output still does the same
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crystal_marie::{compile_source, emulator::ValueMode, test_runner, Options, Source};
+
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+fn root() -> &'static Path {
+    return Path::new(env!("CARGO_MANIFEST_DIR"));
+}
+fn read_source(path: &Path) -> Source {
+    let code = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+    return Source::new(path.display().to_string(), code);
+}
+fn std_lib() -> Source {
+    return read_source(&root().join("std.crmarie"));
+}
+fn examples() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    test_runner::collect_sources(&root().join("examples"), &mut paths).unwrap();
+    paths.sort();
+    assert!(!paths.is_empty(), "no examples found");
+    return paths;
+}
+///examples/x.crmarie has its snapshot in examples/output/x.marie
+fn snapshot_path(example: &Path) -> PathBuf {
+    let name = example.file_stem().unwrap().to_str().unwrap();
+    return example
+        .parent()
+        .unwrap()
+        .join("output")
+        .join(format!("{name}.marie"));
+}
+///std unless the .io file lists other libs
+fn compile_example(example: &Path) -> String {
+    let test = test_runner::read_test(example, ValueMode::Dec).unwrap();
+    let libs = match test.libs {
+        Some(libs) => libs.iter().map(|e| read_source(e)).collect(),
+        None => vec![std_lib()],
+    };
+    let sources: Vec<Source> = std::iter::once(read_source(example)).chain(libs).collect();
+    return match compile_source(&sources, &Options::default()) {
+        Ok(output) => output.code,
+        Err(errors) => panic!("{} doesn't compile: {:?}", example.display(), errors.list),
+    };
+}
+//snapshots are written with or without the last newline, on windows maybe with \r
+fn normalize(code: &str) -> String {
+    return code.replace("\r\n", "\n").trim_end().to_owned();
+}
+fn first_difference(expected: &str, got: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut got_lines = got.lines();
+    for i in 1.. {
+        match (expected_lines.next(), got_lines.next()) {
+            (None, None) => break,
+            (a, b) if a == b => continue,
+            (a, b) => {
+                return format!(
+                    "line {i}: expected {:?}, got {:?}",
+                    a.unwrap_or(""),
+                    b.unwrap_or("")
+                )
+            }
+        }
+    }
+    return String::new();
+}
+
+#[test]
+fn examples_match_snapshots() {
+    let update = env::var(UPDATE_VAR).is_ok_and(|e| e != "0");
+    let mut mismatches = Vec::new();
+    for example in examples() {
+        let code = compile_example(&example);
+        let snapshot = snapshot_path(&example);
+        if update {
+            let old = fs::read_to_string(&snapshot).unwrap_or_default();
+            let newline = if old.ends_with('\n') { "\n" } else { "" };
+            fs::create_dir_all(snapshot.parent().unwrap()).unwrap();
+            fs::write(&snapshot, format!("{code}{newline}")).unwrap();
+            continue;
+        }
+        let expected = match fs::read_to_string(&snapshot) {
+            Ok(expected) => expected,
+            Err(_) => {
+                mismatches.push(format!("{}: no snapshot", snapshot.display()));
+                continue;
+            }
+        };
+        let (expected, code) = (normalize(&expected), normalize(&code));
+        if expected != code {
+            mismatches.push(format!(
+                "{}: {}",
+                snapshot.display(),
+                first_difference(&expected, &code)
+            ));
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "compiled examples differ from snapshots, run with {UPDATE_VAR}=1 if the change is intended:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn examples_pass_their_io_tests() {
+    let files = test_runner::discover(&root().join("examples"), ValueMode::Dec).unwrap();
+    let untested: Vec<_> = examples()
+        .into_iter()
+        .filter(|e| files.iter().all(|f| f.path != *e))
+        .map(|e| e.display().to_string())
+        .collect();
+    assert!(
+        untested.is_empty(),
+        "examples without test cases:\n{}",
+        untested.join("\n")
+    );
+
+    let mut failures = Vec::new();
+    for file in &files {
+        let result = test_runner::run_file(
+            file,
+            &[std_lib()],
+            ValueMode::Dec,
+            test_runner::DEFAULT_MAX_STEPS,
+        );
+        match result {
+            Ok(problems) => failures.extend(
+                problems
+                    .iter()
+                    .map(|e| format!("{}: {e}", file.path.display())),
+            ),
+            Err(err) => failures.push(format!("{}: {err}", file.path.display())),
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}